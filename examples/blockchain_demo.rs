@@ -7,9 +7,9 @@ fn main() {
     let mut dag = BlockDAG::new(3);
 
     println!("1. Setting up accounts:");
-    dag.add_account("alice".to_string(), 1000);
-    dag.add_account("bob".to_string(), 500);
-    dag.add_account("charlie".to_string(), 300);
+    dag.add_account("alice".to_string(), 1000).unwrap();
+    dag.add_account("bob".to_string(), 500).unwrap();
+    dag.add_account("charlie".to_string(), 300).unwrap();
 
     println!("   Alice: 1000, Bob: 500, Charlie: 300\n");
 
@@ -160,8 +160,8 @@ fn main() {
 
     println!("\n8. Demonstrating failed transaction:");
     let mut dag2 = BlockDAG::new(3);
-    dag2.add_account("poor_alice".to_string(), 10);
-    dag2.add_account("rich_bob".to_string(), 1000);
+    dag2.add_account("poor_alice".to_string(), 10).unwrap();
+    dag2.add_account("rich_bob".to_string(), 1000).unwrap();
 
     // try transfer tokens exceed balance
     let tx_fail = Transaction::new(
@@ -197,5 +197,8 @@ fn main() {
         dag2.get_account("rich_bob").unwrap().balance
     );
 
+    println!("\n9. Graphviz DOT export (render with `dot -Tsvg`):");
+    print!("{}", dag.to_dot());
+
     println!("\n=== Demo Complete ===");
 }
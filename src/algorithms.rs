@@ -0,0 +1,545 @@
+use std::borrow::Borrow;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use crate::{Dag, DagNode, EdgeWeight};
+
+/// A path through a `Dag` and its total weight
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DagPath<'a, K, V> {
+    pub nodes: Vec<&'a DagNode<K, V>>, // From first to last node
+    pub weight: u64,                   // Sum of the weights along the path, saturating
+}
+
+/// Schedule of a `Dag` whose nodes are tasks with durations, see `Dag::critical_path`
+#[derive(Debug, Clone)]
+pub struct CriticalPath<'a, K, V> {
+    pub makespan: u64,                   // Time to run every task
+    pub path: Vec<&'a DagNode<K, V>>,    // A chain of tasks without slack
+    pub earliest_start: HashMap<K, u64>, // Earliest start of each task
+    pub latest_start: HashMap<K, u64>,   // Latest start not delaying the makespan
+}
+
+impl<K: Hash + Eq, V> CriticalPath<'_, K, V> {
+    /// How long a task can be delayed without delaying the makespan
+    pub fn slack<Q>(&self, node_id: &Q) -> Option<u64>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        Some(
+            self.latest_start
+                .get(node_id)?
+                .saturating_sub(*self.earliest_start.get(node_id)?),
+        )
+    }
+}
+
+/// Best path weight to each reached node and the node it was reached from
+type PathTable<'a, K> = HashMap<&'a K, (u64, Option<&'a K>)>;
+
+impl<K: Hash + Eq + Clone, V, E> Dag<K, V, E> {
+    /// Longest path by edge weight, e.g. the number of edges for `Dag<K, V>`
+    ///
+    /// Returns `None` for an empty DAG. Ties go to the path ending first in topological order.
+    pub fn longest_path(&self) -> Option<DagPath<'_, K, V>>
+    where
+        E: EdgeWeight,
+    {
+        let table = self.path_table(None, true, |_| 0, E::weight);
+        self.heaviest_path(&table)
+    }
+
+    /// Longest path by node weight, counting every node on the path
+    pub fn longest_path_by<F>(&self, node_weight: F) -> Option<DagPath<'_, K, V>>
+    where
+        F: Fn(&DagNode<K, V>) -> u64,
+    {
+        let table = self.path_table(None, true, node_weight, |_| 0);
+        self.heaviest_path(&table)
+    }
+
+    /// Shortest path between two nodes by edge weight
+    ///
+    /// Returns `None` if either node is missing or `to` is not reachable from `from`.
+    pub fn shortest_path<Q>(&self, from: &Q, to: &Q) -> Option<DagPath<'_, K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        E: EdgeWeight,
+    {
+        let (from, _) = self.nodes.get_key_value(from)?;
+        let (to, _) = self.nodes.get_key_value(to)?;
+        let table = self.path_table(Some(from), false, |_| 0, E::weight);
+        self.path_to(&table, to)
+    }
+
+    /// Shortest path between two nodes by node weight, counting both ends
+    pub fn shortest_path_by<Q, F>(
+        &self,
+        from: &Q,
+        to: &Q,
+        node_weight: F,
+    ) -> Option<DagPath<'_, K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        F: Fn(&DagNode<K, V>) -> u64,
+    {
+        let (from, _) = self.nodes.get_key_value(from)?;
+        let (to, _) = self.nodes.get_key_value(to)?;
+        let table = self.path_table(Some(from), false, node_weight, |_| 0);
+        self.path_to(&table, to)
+    }
+
+    /// Critical path analysis of the DAG as a task graph
+    ///
+    /// Each node is a task taking `duration`, which can start once all its parents are done.
+    /// Times saturate at `u64::MAX` instead of overflowing.
+    pub fn critical_path<F>(&self, duration: F) -> CriticalPath<'_, K, V>
+    where
+        F: Fn(&DagNode<K, V>) -> u64,
+    {
+        let order: Vec<&DagNode<K, V>> = self.topological_iter().collect();
+        let durations: HashMap<&K, u64> = order
+            .iter()
+            .map(|node| (&node.id, duration(node)))
+            .collect();
+
+        let mut earliest_start: HashMap<&K, u64> = HashMap::new();
+        for node in &order {
+            let start = self.parents[&node.id]
+                .iter()
+                .map(|parent| earliest_start[parent].saturating_add(durations[parent]))
+                .max()
+                .unwrap_or(0);
+            earliest_start.insert(&node.id, start);
+        }
+        let makespan = order
+            .iter()
+            .map(|node| earliest_start[&node.id].saturating_add(durations[&node.id]))
+            .max()
+            .unwrap_or(0);
+
+        let mut latest_start: HashMap<&K, u64> = HashMap::new();
+        for node in order.iter().rev() {
+            let finish = self.edges[&node.id]
+                .keys()
+                .map(|child| latest_start[child])
+                .min()
+                .unwrap_or(makespan);
+            latest_start.insert(&node.id, finish.saturating_sub(durations[&node.id]));
+        }
+
+        // Follow tasks without slack, each starting when the previous one ends
+        let critical = |id: &K| earliest_start[id] == latest_start[id];
+        let mut path = Vec::new();
+        let mut next = order
+            .iter()
+            .find(|node| earliest_start[&node.id] == 0 && critical(&node.id))
+            .copied();
+        while let Some(node) = next {
+            path.push(node);
+            let end = earliest_start[&node.id].saturating_add(durations[&node.id]);
+            next = order
+                .iter()
+                .filter(|child| self.edges[&node.id].contains_key(&child.id))
+                .find(|child| earliest_start[&child.id] == end && critical(&child.id))
+                .copied();
+        }
+
+        let owned = |starts: HashMap<&K, u64>| {
+            starts
+                .into_iter()
+                .map(|(id, start)| (id.clone(), start))
+                .collect()
+        };
+        CriticalPath {
+            makespan,
+            path,
+            earliest_start: owned(earliest_start),
+            latest_start: owned(latest_start),
+        }
+    }
+
+    /// Copy of the DAG without edges implied by other paths
+    pub fn transitive_reduction(&self) -> Self
+    where
+        V: Clone,
+        E: Clone,
+    {
+        let reach = self.reachability();
+        let edges = self
+            .edges()
+            .filter(|(from, to, _)| {
+                !self.edges[*from]
+                    .keys()
+                    .any(|child| child != *to && reach[child].contains(to))
+            })
+            .map(|(from, to, data)| (from.clone(), to.clone(), data.clone()))
+            .collect::<Vec<_>>();
+        self.rebuilt(edges)
+    }
+
+    /// Copy of the DAG with an edge from every node to each of its descendants
+    ///
+    /// Existing edges keep their data, added ones get `E::default()`.
+    pub fn transitive_closure(&self) -> Self
+    where
+        V: Clone,
+        E: Clone + Default,
+    {
+        let reach = self.reachability();
+        let edges = reach
+            .iter()
+            .flat_map(|(from, descendants)| descendants.iter().map(move |to| (*from, *to)))
+            .map(|(from, to)| {
+                let data = self.edges[from].get(to).cloned().unwrap_or_default();
+                (from.clone(), to.clone(), data)
+            })
+            .collect::<Vec<_>>();
+        self.rebuilt(edges)
+    }
+
+    /// Lowest common ancestors of two nodes
+    ///
+    /// A node counts as its own ancestor, so if `a` reaches `b` the result is `[a]`. Several
+    /// nodes are returned when no single one is lowest, in insertion order. Returns `None`
+    /// if either node is missing.
+    pub fn lowest_common_ancestors<Q>(&self, a: &Q, b: &Q) -> Option<Vec<&DagNode<K, V>>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let self_and_ancestors = |id: &Q| -> Option<HashSet<&K>> {
+            let (id, node) = self.nodes.get_key_value(id)?;
+            let mut ids: HashSet<&K> = self
+                .ancestors::<K>(id)?
+                .into_iter()
+                .map(|n| &n.id)
+                .collect();
+            ids.insert(&node.id);
+            Some(ids)
+        };
+        let of_a = self_and_ancestors(a)?;
+        let of_b = self_and_ancestors(b)?;
+        let common: HashSet<&K> = of_a.intersection(&of_b).copied().collect();
+
+        // Any common ancestor below another one is reached through one of its children
+        let mut lowest: Vec<&DagNode<K, V>> = common
+            .iter()
+            .filter(|id| {
+                !self
+                    .edges
+                    .get::<K>(id)
+                    .unwrap()
+                    .keys()
+                    .any(|child| common.contains(child))
+            })
+            .map(|id| self.nodes.get::<K>(id).unwrap())
+            .collect();
+        lowest.sort_by_key(|node| self.sequence.get::<K>(&node.id).unwrap());
+        Some(lowest)
+    }
+
+    /// Nodes grouped by depth, the length of the longest path from a root
+    ///
+    /// Layer 0 holds the roots, and every node comes after all of its parents' layers. Each
+    /// layer is in insertion order.
+    pub fn layers(&self) -> Vec<Vec<&DagNode<K, V>>> {
+        let mut depth: HashMap<&K, usize> = HashMap::new();
+        let mut layers: Vec<Vec<&DagNode<K, V>>> = Vec::new();
+        for node in self.topological_iter() {
+            let d = self.parents[&node.id]
+                .iter()
+                .map(|parent| depth[parent] + 1)
+                .max()
+                .unwrap_or(0);
+            depth.insert(&node.id, d);
+            if layers.len() == d {
+                layers.push(Vec::new());
+            }
+            layers[d].push(node);
+        }
+        for layer in &mut layers {
+            layer.sort_by_key(|node| self.sequence[&node.id]);
+        }
+        layers
+    }
+
+    /// Best path weights in topological order, from `source` or from every node if `None`
+    ///
+    /// Weights saturate at `u64::MAX`, so a longest path through huge weights is still found.
+    fn path_table<'a>(
+        &'a self,
+        source: Option<&'a K>,
+        longest: bool,
+        node_weight: impl Fn(&DagNode<K, V>) -> u64,
+        edge_weight: impl Fn(&E) -> u64,
+    ) -> PathTable<'a, K> {
+        let mut table: PathTable<'a, K> = HashMap::new();
+        if let Some(source) = source {
+            table.insert(source, (node_weight(&self.nodes[source]), None));
+        }
+        for node in self.topological_iter() {
+            let id = &node.id;
+            if source.is_none() {
+                table.entry(id).or_insert((node_weight(node), None));
+            }
+            let Some(&(weight, _)) = table.get(id) else {
+                continue;
+            };
+            for (child, data) in &self.edges[id] {
+                let candidate = weight
+                    .saturating_add(edge_weight(data))
+                    .saturating_add(node_weight(&self.nodes[child]));
+                // The first parent in topological order wins ties
+                let better = match table.get(child) {
+                    None => true,
+                    Some(&(current, _)) if longest => candidate > current,
+                    Some(&(current, _)) => candidate < current,
+                };
+                if better {
+                    table.insert(child, (candidate, Some(id)));
+                }
+            }
+        }
+        table
+    }
+
+    /// Heaviest path in a table, ending at the first such node in topological order
+    fn heaviest_path<'a>(&'a self, table: &PathTable<'a, K>) -> Option<DagPath<'a, K, V>> {
+        let mut end: Option<(&K, u64)> = None;
+        for node in self.topological_iter() {
+            let weight = table[&node.id].0;
+            if end.is_none_or(|(_, best)| weight > best) {
+                end = Some((&node.id, weight));
+            }
+        }
+        self.path_to(table, end?.0)
+    }
+
+    /// Walk a table back from `to`
+    fn path_to<'a>(&'a self, table: &PathTable<'a, K>, to: &'a K) -> Option<DagPath<'a, K, V>> {
+        let &(weight, _) = table.get(to)?;
+        let mut id = to;
+        let mut nodes = vec![&self.nodes[id]];
+        while let Some((_, Some(previous))) = table.get(id) {
+            id = previous;
+            nodes.push(&self.nodes[id]);
+        }
+        nodes.reverse();
+        Some(DagPath { nodes, weight })
+    }
+
+    /// Descendants of every node
+    fn reachability(&self) -> HashMap<&K, HashSet<&K>> {
+        let order: Vec<&K> = self.topological_iter().map(|node| &node.id).collect();
+        let mut reach: HashMap<&K, HashSet<&K>> = HashMap::new();
+        for id in order.into_iter().rev() {
+            let mut descendants = HashSet::new();
+            for child in self.edges[id].keys() {
+                descendants.insert(child);
+                descendants.extend(&reach[child]);
+            }
+            reach.insert(id, descendants);
+        }
+        reach
+    }
+
+    /// New DAG with the same nodes, in insertion order, and the given edges
+    fn rebuilt(&self, edges: Vec<(K, K, E)>) -> Self
+    where
+        V: Clone,
+    {
+        let mut dag = Dag::default();
+        for node in self.nodes_by_sequence() {
+            dag.insert_node(node.clone());
+        }
+        dag.insert_edges_batch(edges)
+            .unwrap_or_else(|_| unreachable!("rebuilt DAG has a cycle"));
+        dag
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Dag, DagNode, Node};
+
+    fn ids<K: ToString, V>(nodes: &[&DagNode<K, V>]) -> Vec<String> {
+        nodes.iter().map(|node| node.id.to_string()).collect()
+    }
+
+    /// Build pipeline with task durations as payloads
+    fn pipeline() -> Dag<&'static str, u64> {
+        let mut dag = Dag::default();
+        for (id, duration) in [
+            ("fetch", 4u64),
+            ("build", 3),
+            ("test", 5),
+            ("lint", 2),
+            ("deploy", 1),
+            ("docs", 1),
+        ] {
+            dag.add_node(DagNode::new(id, duration)).unwrap();
+        }
+        for (from, to) in [
+            ("fetch", "build"),
+            ("build", "test"),
+            ("build", "lint"),
+            ("test", "deploy"),
+            ("lint", "deploy"),
+        ] {
+            dag.add_edge(&from, &to).unwrap();
+        }
+        dag
+    }
+
+    fn diamond() -> Dag {
+        let mut dag = Dag::new();
+        for id in ["A", "B", "C", "D"] {
+            dag.add_node(Node::new(id, "")).unwrap();
+        }
+        for (from, to) in [("A", "B"), ("A", "C"), ("B", "D"), ("C", "D")] {
+            dag.add_edge(from, to).unwrap();
+        }
+        dag
+    }
+
+    #[test]
+    fn test_paths_by_edge_weight() {
+        let mut dag: Dag<String, String, u64> = Dag::default();
+        for id in ["A", "B", "C", "D", "E"] {
+            dag.add_node(Node::new(id, "")).unwrap();
+        }
+        for (from, to, weight) in [
+            ("A", "B", 1),
+            ("A", "C", 5),
+            ("B", "D", 1),
+            ("C", "D", 1),
+            ("D", "E", 2),
+        ] {
+            dag.add_edge_with(from, to, weight).unwrap();
+        }
+
+        let longest = dag.longest_path().unwrap();
+        assert_eq!(ids(&longest.nodes), ["A", "C", "D", "E"]);
+        assert_eq!(longest.weight, 8);
+
+        let shortest = dag.shortest_path("A", "E").unwrap();
+        assert_eq!(ids(&shortest.nodes), ["A", "B", "D", "E"]);
+        assert_eq!(shortest.weight, 4);
+        assert_eq!(dag.shortest_path("B", "B").unwrap().weight, 0);
+        assert!(dag.shortest_path("E", "A").is_none());
+        assert!(dag.shortest_path("A", "missing").is_none());
+
+        // Unit edges count hops
+        let dag = diamond();
+        let longest = dag.longest_path().unwrap();
+        assert_eq!(longest.weight, 2);
+        assert_eq!(ids(&longest.nodes), ["A", "B", "D"]);
+        assert!(Dag::new().longest_path().is_none());
+    }
+
+    #[test]
+    fn test_paths_by_node_weight() {
+        let dag = pipeline();
+        let longest = dag.longest_path_by(|node| node.data).unwrap();
+        assert_eq!(ids(&longest.nodes), ["fetch", "build", "test", "deploy"]);
+        assert_eq!(longest.weight, 13);
+
+        let shortest = dag
+            .shortest_path_by(&"fetch", &"deploy", |node| node.data)
+            .unwrap();
+        assert_eq!(ids(&shortest.nodes), ["fetch", "build", "lint", "deploy"]);
+        assert_eq!(shortest.weight, 10);
+    }
+
+    #[test]
+    fn test_critical_path() {
+        let dag = pipeline();
+        let schedule = dag.critical_path(|node| node.data);
+        assert_eq!(schedule.makespan, 13);
+        assert_eq!(ids(&schedule.path), ["fetch", "build", "test", "deploy"]);
+        assert_eq!(schedule.earliest_start["deploy"], 12);
+        assert_eq!(schedule.latest_start["lint"], 10);
+        assert_eq!(schedule.slack(&"lint"), Some(3));
+        assert_eq!(schedule.slack(&"docs"), Some(12));
+        assert_eq!(schedule.slack(&"test"), Some(0));
+        assert_eq!(schedule.slack(&"missing"), None);
+
+        let empty: Dag<&str, u64> = Dag::default();
+        let schedule = empty.critical_path(|node| node.data);
+        assert_eq!(schedule.makespan, 0);
+        assert!(schedule.path.is_empty());
+    }
+
+    #[test]
+    fn test_weights_saturate() {
+        let dag = pipeline();
+        let longest = dag.longest_path_by(|_| u64::MAX).unwrap();
+        assert_eq!(longest.weight, u64::MAX);
+        assert_eq!(ids(&longest.nodes)[0], "fetch");
+        let shortest = dag
+            .shortest_path_by(&"fetch", &"deploy", |_| u64::MAX)
+            .unwrap();
+        assert_eq!(shortest.weight, u64::MAX);
+
+        let schedule = dag.critical_path(|_| u64::MAX);
+        assert_eq!(schedule.makespan, u64::MAX);
+        assert_eq!(schedule.earliest_start["deploy"], u64::MAX);
+        assert_eq!(schedule.slack(&"fetch"), Some(0));
+        assert_eq!(ids(&schedule.path)[0], "fetch");
+    }
+
+    #[test]
+    fn test_transitive_reduction_and_closure() {
+        let mut dag = diamond();
+        dag.add_edge("A", "D").unwrap();
+
+        let reduced = dag.transitive_reduction();
+        assert_eq!(reduced.edge_count(), 4);
+        assert!(reduced.get_edge("A", "D").is_none());
+        assert_eq!(reduced.node_count(), 4);
+
+        let closure = diamond().transitive_closure();
+        assert_eq!(closure.edge_count(), 5);
+        assert!(closure.get_edge("A", "D").is_some());
+        assert_eq!(closure.transitive_reduction().edge_count(), 4);
+
+        // Insertion order survives the rebuild
+        let order: Vec<String> = closure.topological_iter().map(|n| n.id.clone()).collect();
+        assert_eq!(order, ["A", "B", "C", "D"]);
+    }
+
+    #[test]
+    fn test_lowest_common_ancestors() {
+        let mut dag = diamond();
+        assert_eq!(ids(&dag.lowest_common_ancestors("B", "C").unwrap()), ["A"]);
+        assert_eq!(ids(&dag.lowest_common_ancestors("A", "D").unwrap()), ["A"]);
+        assert_eq!(ids(&dag.lowest_common_ancestors("D", "D").unwrap()), ["D"]);
+        assert!(dag.lowest_common_ancestors("B", "missing").is_none());
+
+        dag.add_node(Node::new("X", "")).unwrap();
+        dag.add_edge("X", "B").unwrap();
+        dag.add_edge("X", "C").unwrap();
+        assert_eq!(
+            ids(&dag.lowest_common_ancestors("B", "C").unwrap()),
+            ["A", "X"]
+        );
+        assert_eq!(ids(&dag.lowest_common_ancestors("B", "D").unwrap()), ["B"]);
+
+        dag.add_node(Node::new("Y", "")).unwrap();
+        assert!(dag.lowest_common_ancestors("Y", "D").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_layers() {
+        let mut dag = diamond();
+        dag.add_node(Node::new("E", "")).unwrap();
+        dag.add_edge("A", "D").unwrap();
+        let layers: Vec<Vec<String>> = dag.layers().iter().map(|layer| ids(layer)).collect();
+        assert_eq!(layers, [vec!["A", "E"], vec!["B", "C"], vec!["D"]]);
+        assert!(Dag::new().layers().is_empty());
+    }
+}
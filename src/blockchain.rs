@@ -1,9 +1,117 @@
 use std::cmp::Reverse;
-use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet, VecDeque};
 use std::fmt;
 
+pub mod codec;
+mod genesis;
+mod hashing;
+mod index;
+#[cfg(feature = "serde")]
+mod json;
+mod params;
+mod render;
+mod storage;
+
+use hashing::Fnv1a;
+
+pub use codec::{CodecError, Decode, Encode};
+pub use genesis::{GENESIS_HASH, GenesisConfig};
+pub use index::{AddressTxEntry, TxDirection, TxIndex};
+#[cfg(feature = "serde")]
+pub use json::JSON_SCHEMA_VERSION;
+pub use params::{
+    ConsensusParams, DEFAULT_FINALITY_DEPTH, DEFAULT_K, DEFAULT_MAX_BLOCK_PARENTS,
+    DEFAULT_MERGE_DEPTH, DEFAULT_MERGESET_SIZE_LIMIT, DEFAULT_SECURITY_DELTA, recommended_k,
+};
+pub use render::RenderOptions;
+pub use storage::{BatchOp, FileStore, MemoryStore, Store, WriteBatch};
+
+/// Parents a normalizing DAG accepts before normalization, as a multiple of the limit
+const NORMALIZED_PARENTS_FACTOR: usize = 4;
+
+/// Reason a block was rejected by `BlockDAG::add_block`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleError {
+    DuplicateBlock(String),
+    MissingParent(String),
+    PrunedParent(String),
+    TooManyParents {
+        count: usize,
+        max: usize,
+    },
+    MergesetTooLarge {
+        size: usize,
+        limit: usize,
+    },
+    RedundantParent {
+        parent: String,
+        descendant_parent: String,
+    },
+    FinalityViolation {
+        finalized_block: String,
+        finality_depth: u64,
+    },
+    MergeDepthViolation {
+        block: String,
+        merged_block: String,
+        merge_depth_root: String,
+    },
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuleError::DuplicateBlock(hash) => write!(f, "Block '{}' already exists", hash),
+            RuleError::MissingParent(parent) => {
+                write!(f, "Parent block '{}' does not exist", parent)
+            }
+            RuleError::PrunedParent(parent) => {
+                write!(f, "Parent block '{}' is below the pruning point", parent)
+            }
+            RuleError::TooManyParents { count, max } => {
+                write!(f, "Block has {} parents, at most {} allowed", count, max)
+            }
+            RuleError::MergesetTooLarge { size, limit } => {
+                write!(
+                    f,
+                    "Block mergeset has {} blocks, at most {} allowed",
+                    size, limit
+                )
+            }
+            RuleError::RedundantParent {
+                parent,
+                descendant_parent,
+            } => write!(
+                f,
+                "Parent block '{}' is an ancestor of parent block '{}'",
+                parent, descendant_parent
+            ),
+            RuleError::FinalityViolation {
+                finalized_block,
+                finality_depth,
+            } => write!(
+                f,
+                "Block would reorganize finalized block '{}' (finality depth {})",
+                finalized_block, finality_depth
+            ),
+            RuleError::MergeDepthViolation {
+                block,
+                merged_block,
+                merge_depth_root,
+            } => write!(
+                f,
+                "Block '{}' merges block '{}' which is deeper than the merge depth root '{}'",
+                block, merged_block, merge_depth_root
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RuleError {}
+
 /// Transaction status
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TxStatus {
     Pending,
     Executed,
@@ -13,11 +121,13 @@ pub enum TxStatus {
 
 /// Transaction structure
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Transaction {
     pub id: String,
     pub from: String,
     pub to: String,
     pub amount: u64,
+    pub fee: u64, // Paid by the sender on top of amount, burned on execution
     pub nonce: u64,
     pub status: TxStatus,
 }
@@ -29,21 +139,75 @@ impl Transaction {
             from,
             to,
             amount,
+            fee: 0,
             nonce,
             status: TxStatus::Pending,
         }
     }
+
+    /// Set the fee paid by the sender
+    pub fn with_fee(mut self, fee: u64) -> Self {
+        self.fee = fee;
+        self
+    }
+}
+
+/// Execution receipt of a transaction, recorded per (chain block, tx id)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TxReceipt {
+    pub tx_id: String,
+    pub block_hash: String,      // Block containing the transaction
+    pub accepting_block: String, // Chain block whose execution produced the receipt
+    pub status: TxStatus,
+    pub fee: u64,
+    pub balance_deltas: BTreeMap<String, i128>, // address -> balance change
+}
+
+impl TxReceipt {
+    /// Whether the transaction was applied to the state
+    pub fn is_accepted(&self) -> bool {
+        self.status == TxStatus::Executed
+    }
+
+    /// Reason the transaction was rejected, if it was
+    pub fn failure_reason(&self) -> Option<&str> {
+        match &self.status {
+            TxStatus::Failed(reason) => Some(reason),
+            _ => None,
+        }
+    }
+}
+
+/// Transactions of one block accepted by a merging chain block
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MergedBlockAcceptance {
+    pub block_hash: String,
+    pub accepted_tx_ids: Vec<String>,
 }
 
 /// Block color (GHOSTDAG protocol)
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BlockColor {
     Blue, // On the main chain
     Red,  // Not on the main chain
 }
 
+/// Block header, kept for blocks whose body was pruned
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BlockHeader {
+    pub hash: String,
+    pub parent_hashes: Vec<String>,
+    pub timestamp: u64,
+    pub height: u64,
+}
+
 /// Block structure
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Block {
     pub hash: String,
     pub parent_hashes: Vec<String>, // Can have multiple parent blocks in DAG
@@ -51,7 +215,7 @@ pub struct Block {
     pub timestamp: u64,
     pub height: u64,
     pub color: BlockColor,
-    pub weight: u64, // Cumulative weight
+    pub weight: u64, // Position in the blue order, 0 for red blocks
 }
 
 impl Block {
@@ -74,7 +238,7 @@ impl Block {
 
     pub fn genesis() -> Self {
         Self {
-            hash: "genesis".to_string(),
+            hash: GENESIS_HASH.to_string(),
             parent_hashes: vec![],
             transactions: vec![],
             timestamp: 0,
@@ -83,10 +247,21 @@ impl Block {
             weight: 1,
         }
     }
+
+    /// Get the header of this block
+    pub fn header(&self) -> BlockHeader {
+        BlockHeader {
+            hash: self.hash.clone(),
+            parent_hashes: self.parent_hashes.clone(),
+            timestamp: self.timestamp,
+            height: self.height,
+        }
+    }
 }
 
 /// Account state
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Account {
     pub address: String,
     pub balance: u64,
@@ -103,36 +278,186 @@ impl Account {
     }
 }
 
+/// Whole-DAG export: consensus parameters, blocks, pruned headers, accounts and pruning data
+///
+/// Execution receipts and the transaction index are not included.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BlockDagSnapshot {
+    pub params: ConsensusParams,
+    pub blocks: Vec<Block>,               // Sorted by (timestamp, hash)
+    pub pruned_headers: Vec<BlockHeader>, // Sorted by hash
+    pub accounts: Vec<Account>,           // Sorted by address
+    pub pruning_point: String,
+    pub pruned_blue_count: u64,
+    pub pruning_point_accounts: Vec<Account>, // Sorted by address
+    pub pruning_point_commitment: u64,
+}
+
 /// BlockDAG - DAG-based blockchain
 pub struct BlockDAG {
     blocks: HashMap<String, Block>,
     children_mapping: HashMap<String, HashSet<String>>, // Child block mapping
     accounts: HashMap<String, Account>,
-    k: usize, // GHOSTDAG parameter, controls anticone size
+    params: ConsensusParams,
+    auto_normalize_parents: bool, // Drop redundant parents instead of rejecting the block
+    test_mode: bool,              // Allow adding accounts after genesis setup
+
+    // Pruning
+    pruning_point: String, // Root of the retained DAG, everything below it is pruned
+    pruned_blue_count: u64, // Number of blue blocks ordered before the pruning point
+    pruned_headers: HashMap<String, BlockHeader>, // Headers of pruned blocks
+    pruning_point_accounts: HashMap<String, Account>, // Account state execution starts from
+    pruning_point_commitment: u64, // Commitment to the account state at the pruning point
+
+    // Execution results
+    receipts: HashMap<String, Vec<TxReceipt>>, // Chain block -> receipts in execution order
+    receipt_locations: HashMap<String, String>, // Tx id -> chain block holding its latest receipt
+    acceptance: HashMap<String, Vec<MergedBlockAcceptance>>, // Chain block -> merged blocks
+    tx_index: Option<TxIndex>,                 // Optional transaction indexer
 }
 
 impl BlockDAG {
+    /// Create a BlockDAG with the given k and otherwise default parameters
+    ///
+    /// No network delay is assumed and the mergeset size limit grows with k, so the
+    /// parameters pass `ConsensusParams::validate` for any k.
     pub fn new(k: usize) -> Self {
+        let params = ConsensusParams {
+            k,
+            network_delay_ms: 0,
+            mergeset_size_limit: DEFAULT_MERGESET_SIZE_LIMIT.max(k.saturating_add(1)),
+            ..ConsensusParams::default()
+        }
+        .seal_genesis();
+        debug_assert!(params.validate().is_ok());
+        Self::from_params(params)
+    }
+
+    /// Create a BlockDAG with the given consensus parameters
+    pub fn with_params(params: ConsensusParams) -> Result<Self, String> {
+        params.validate()?;
+        Ok(Self::from_params(params))
+    }
+
+    fn from_params(params: ConsensusParams) -> Self {
+        let genesis = params.genesis.block();
+        let accounts = genesis_accounts(&params.genesis);
+        let pruning_point_commitment = commitment_of(&accounts);
+
         let mut dag = Self {
             blocks: HashMap::new(),
             children_mapping: HashMap::new(),
-            accounts: HashMap::new(),
-            k,
+            pruning_point_accounts: accounts.clone(),
+            accounts,
+            receipts: HashMap::new(),
+            receipt_locations: HashMap::new(),
+            acceptance: HashMap::new(),
+            tx_index: None,
+            params,
+            auto_normalize_parents: false,
+            test_mode: false,
+            pruning_point: genesis.hash.clone(),
+            pruned_blue_count: 0,
+            pruned_headers: HashMap::new(),
+            pruning_point_commitment,
         };
 
         // Add genesis block
-        let genesis = Block::genesis();
-        dag.blocks.insert("genesis".to_string(), genesis);
         dag.children_mapping
-            .insert("genesis".to_string(), HashSet::new());
+            .insert(genesis.hash.clone(), HashSet::new());
+        dag.blocks.insert(genesis.hash.clone(), genesis);
 
         dag
     }
 
+    /// Get the consensus parameters
+    pub fn params(&self) -> &ConsensusParams {
+        &self.params
+    }
+
+    /// Set the finality depth, in blue score
+    pub fn with_finality_depth(self, finality_depth: u64) -> Result<Self, String> {
+        self.with_params_changed(|params| params.finality_depth = finality_depth)
+    }
+
+    /// Set the merge depth bound, in blue score
+    pub fn with_merge_depth(self, merge_depth: u64) -> Result<Self, String> {
+        self.with_params_changed(|params| params.merge_depth = merge_depth)
+    }
+
+    /// Set the maximum number of parents a block may reference
+    pub fn with_max_block_parents(self, max_block_parents: usize) -> Result<Self, String> {
+        self.with_params_changed(|params| params.max_block_parents = max_block_parents)
+    }
+
+    /// Set the maximum mergeset size of a block
+    pub fn with_mergeset_size_limit(self, mergeset_size_limit: usize) -> Result<Self, String> {
+        self.with_params_changed(|params| params.mergeset_size_limit = mergeset_size_limit)
+    }
+
+    /// Change the parameters, resealing the genesis, unless they no longer validate
+    fn with_params_changed(
+        mut self,
+        change: impl FnOnce(&mut ConsensusParams),
+    ) -> Result<Self, String> {
+        let mut params = self.params.clone();
+        change(&mut params);
+        let params = params.seal_genesis();
+        params.validate()?;
+        self.params = params;
+        Ok(self)
+    }
+
+    /// Drop redundant parents of added blocks instead of rejecting them
+    pub fn with_parent_normalization(mut self, normalize_parents: bool) -> Self {
+        self.auto_normalize_parents = normalize_parents;
+        self
+    }
+
+    /// Allow adding accounts at any time, for tests and simulations
+    pub fn with_test_mode(mut self, test_mode: bool) -> Self {
+        self.test_mode = test_mode;
+        self
+    }
+
+    /// Get the genesis definition
+    pub fn genesis(&self) -> &GenesisConfig {
+        &self.params.genesis
+    }
+
     /// Add account
-    pub fn add_account(&mut self, address: String, balance: u64) {
-        self.accounts
-            .insert(address.clone(), Account::new(address, balance));
+    ///
+    /// Only allowed during genesis setup (before any block is added on top of genesis)
+    /// or in test mode. During genesis setup the account becomes a genesis allocation:
+    /// the genesis is resealed and the initial state rebuilt from it, so
+    /// `genesis().commitment()` changes and stores saved before no longer load.
+    pub fn add_account(&mut self, address: String, balance: u64) -> Result<(), String> {
+        let genesis_setup = self.blocks.len() == 1 && self.pruned_headers.is_empty();
+        if genesis_setup {
+            let genesis = self
+                .params
+                .genesis
+                .clone()
+                .with_allocation(address, balance);
+            self.params = self.params.clone().with_genesis(genesis);
+            self.accounts = genesis_accounts(&self.params.genesis);
+            self.pruning_point_accounts = self.accounts.clone();
+            self.pruning_point_commitment = commitment_of(&self.accounts);
+            return Ok(());
+        }
+        if !self.test_mode {
+            return Err(format!(
+                "Cannot add account '{}': accounts can only be added during genesis setup",
+                address
+            ));
+        }
+        let account = Account::new(address.clone(), balance);
+        self.pruning_point_accounts
+            .insert(address.clone(), account.clone());
+        self.pruning_point_commitment = commitment_of(&self.pruning_point_accounts);
+        self.accounts.insert(address, account);
+        Ok(())
     }
 
     /// Get account
@@ -140,15 +465,101 @@ impl BlockDAG {
         self.accounts.get(address)
     }
 
+    /// Enable the transaction index, indexing all blocks added so far
+    pub fn enable_tx_index(&mut self) {
+        if self.tx_index.is_some() {
+            return;
+        }
+        let mut index = TxIndex::new();
+        let mut blocks: Vec<&Block> = self.blocks.values().collect();
+        blocks.sort_by(|a, b| {
+            a.timestamp
+                .cmp(&b.timestamp)
+                .then_with(|| a.hash.cmp(&b.hash))
+        });
+        for block in blocks {
+            index.index_block(block);
+            for tx in &block.transactions {
+                if let Some(receipt) = self.receipt(&tx.id) {
+                    index.record_receipt(&tx.from, &tx.to, receipt);
+                }
+            }
+        }
+        self.tx_index = Some(index);
+    }
+
+    /// Get the transaction index, if enabled
+    pub fn tx_index(&self) -> Option<&TxIndex> {
+        self.tx_index.as_ref()
+    }
+
     /// Add block to DAG
-    pub fn add_block(&mut self, block: Block) -> Result<(), String> {
+    ///
+    /// Duplicate parent references are dropped. The block is rejected if it is already
+    /// known (pruned blocks included), if a parent is an ancestor of another parent
+    /// (unless parent normalization is enabled, which drops such parents), if it has
+    /// too many parents or too large a mergeset, if it would move the finality point
+    /// in the blue order, or if it violates the merge depth bound.
+    pub fn add_block(&mut self, mut block: Block) -> Result<(), RuleError> {
+        if self.blocks.contains_key(&block.hash) || self.pruned_headers.contains_key(&block.hash) {
+            return Err(RuleError::DuplicateBlock(block.hash));
+        }
+
+        // Deduplicate parents, keeping the first occurrence
+        let mut seen = HashSet::new();
+        block.parent_hashes.retain(|p| seen.insert(p.clone()));
+
+        // Bound the parents before walking the past of each one. Normalization may drop
+        // redundant parents, so it gets some slack before the exact check below.
+        let max_parents = if self.auto_normalize_parents {
+            self.params
+                .max_block_parents
+                .saturating_mul(NORMALIZED_PARENTS_FACTOR)
+        } else {
+            self.params.max_block_parents
+        };
+        if block.parent_hashes.len() > max_parents {
+            return Err(RuleError::TooManyParents {
+                count: block.parent_hashes.len(),
+                max: self.params.max_block_parents,
+            });
+        }
+
         // Verify parent blocks exist
         for parent in &block.parent_hashes {
+            if self.pruned_headers.contains_key(parent) {
+                return Err(RuleError::PrunedParent(parent.clone()));
+            }
             if !self.blocks.contains_key(parent) {
-                return Err(format!("Parent block '{}' does not exist", parent));
+                return Err(RuleError::MissingParent(parent.clone()));
             }
         }
 
+        // Parents must form an antichain
+        if self.auto_normalize_parents {
+            block.parent_hashes = self.normalize_parent_list(&block.parent_hashes);
+        } else {
+            self.check_parents_antichain(&block.parent_hashes)?;
+        }
+
+        // Counted again once redundant parents are dropped
+        if block.parent_hashes.len() > self.params.max_block_parents {
+            return Err(RuleError::TooManyParents {
+                count: block.parent_hashes.len(),
+                max: self.params.max_block_parents,
+            });
+        }
+
+        let mergeset_size = self.mergeset_of(&block.parent_hashes).len();
+        if mergeset_size > self.params.mergeset_size_limit {
+            return Err(RuleError::MergesetTooLarge {
+                size: mergeset_size,
+                limit: self.params.mergeset_size_limit,
+            });
+        }
+
+        let finality_point = self.finality_point().map(|b| (b.hash.clone(), b.weight));
+
         let hash = block.hash.clone();
 
         // Update parent blocks' children list
@@ -164,14 +575,329 @@ impl BlockDAG {
         self.children_mapping.insert(hash.clone(), HashSet::new());
 
         // Recalculate GHOSTDAG ordering
-        self.update_ghostdag_ordering()?;
+        self.update_ghostdag_ordering();
+
+        if let Err(e) = self
+            .check_finality(finality_point)
+            .and_then(|_| self.check_merge_depth(&hash))
+        {
+            self.remove_block(&hash);
+            self.update_ghostdag_ordering();
+            return Err(e);
+        }
+
+        if let Some(index) = &mut self.tx_index {
+            index.index_block(&self.blocks[&hash]);
+        }
+
+        Ok(())
+    }
+
+    /// Verify no parent is reachable from another parent
+    fn check_parents_antichain(&self, parents: &[String]) -> Result<(), RuleError> {
+        for descendant_parent in parents {
+            let ancestors = self.get_ancestors(descendant_parent);
+            if let Some(parent) = parents.iter().find(|p| ancestors.contains(*p)) {
+                return Err(RuleError::RedundantParent {
+                    parent: parent.clone(),
+                    descendant_parent: descendant_parent.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Normalize a parent list for block template building: duplicates, unknown
+    /// blocks and parents that are ancestors of other parents are dropped,
+    /// keeping the original order
+    pub fn normalize_parents(&self, parents: &[String]) -> Vec<String> {
+        let mut seen = HashSet::new();
+        let known: Vec<String> = parents
+            .iter()
+            .filter(|p| self.blocks.contains_key(*p) && seen.insert((*p).clone()))
+            .cloned()
+            .collect();
+        self.normalize_parent_list(&known)
+    }
+
+    /// Drop parents that are ancestors of other parents
+    fn normalize_parent_list(&self, parents: &[String]) -> Vec<String> {
+        let mut redundant = HashSet::new();
+        for parent in parents {
+            redundant.extend(self.get_ancestors(parent));
+        }
+        parents
+            .iter()
+            .filter(|p| !redundant.contains(*p))
+            .cloned()
+            .collect()
+    }
+
+    /// Verify the previous finality point, given as (hash, weight), kept its place in
+    /// the blue order
+    fn check_finality(&self, finality_point: Option<(String, u64)>) -> Result<(), RuleError> {
+        let Some((hash, weight)) = finality_point else {
+            return Ok(());
+        };
+        let block = &self.blocks[&hash];
+        if block.color != BlockColor::Blue || block.weight != weight {
+            return Err(RuleError::FinalityViolation {
+                finalized_block: hash,
+                finality_depth: self.params.finality_depth,
+            });
+        }
+        Ok(())
+    }
+
+    /// Verify the merge depth bound rule for a block
+    ///
+    /// Every red block in the mergeset must be in the future of the merge depth
+    /// root, unless it is in the past of a blue mergeset block that is (a
+    /// "kosherizing" block).
+    fn check_merge_depth(&self, hash: &str) -> Result<(), RuleError> {
+        let root = match self.merge_depth_root(hash) {
+            Some(root) => root,
+            None => return Ok(()),
+        };
+
+        let mut reds = Vec::new();
+        let mut kosherizing_blues = Vec::new();
+        for merged in self.mergeset(hash) {
+            let in_future_of_root = self.get_ancestors(&merged).contains(&root);
+            match self.blocks[&merged].color {
+                BlockColor::Red if !in_future_of_root => reds.push(merged),
+                BlockColor::Blue if in_future_of_root => kosherizing_blues.push(merged),
+                _ => {}
+            }
+        }
+
+        for red in reds {
+            let kosherized = kosherizing_blues
+                .iter()
+                .any(|blue| self.get_ancestors(blue).contains(&red));
+            if !kosherized {
+                return Err(RuleError::MergeDepthViolation {
+                    block: hash.to_string(),
+                    merged_block: red,
+                    merge_depth_root: root,
+                });
+            }
+        }
 
         Ok(())
     }
 
+    /// Get the selected parent of a block: its parent with the highest weight
+    pub fn selected_parent(&self, hash: &str) -> Option<&str> {
+        self.selected_parent_of(&self.blocks.get(hash)?.parent_hashes)
+    }
+
+    /// Pick the selected parent among a set of parents
+    fn selected_parent_of(&self, parents: &[String]) -> Option<&str> {
+        parents
+            .iter()
+            .filter_map(|p| self.blocks.get(p))
+            .max_by(|a, b| a.weight.cmp(&b.weight).then_with(|| b.hash.cmp(&a.hash)))
+            .map(|b| b.hash.as_str())
+    }
+
+    /// Get the mergeset of a block: blocks in its past that are not in the past of
+    /// its selected parent (nor the selected parent itself)
+    pub fn mergeset(&self, hash: &str) -> HashSet<String> {
+        match self.blocks.get(hash) {
+            Some(block) => self.mergeset_of(&block.parent_hashes),
+            None => HashSet::new(),
+        }
+    }
+
+    /// Compute the mergeset of a block with the given parents
+    fn mergeset_of(&self, parents: &[String]) -> HashSet<String> {
+        let mut mergeset: HashSet<String> = parents.iter().cloned().collect();
+        for parent in parents {
+            mergeset.extend(self.get_ancestors(parent));
+        }
+        if let Some(selected_parent) = self.selected_parent_of(parents) {
+            for ancestor in self.get_ancestors(selected_parent) {
+                mergeset.remove(&ancestor);
+            }
+            mergeset.remove(selected_parent);
+        }
+        mergeset
+    }
+
+    /// Get the merge depth root of a block: the first block on its selected chain
+    /// at least `merge_depth` blue score below its selected parent
+    fn merge_depth_root(&self, hash: &str) -> Option<String> {
+        let selected_parent = self.selected_parent(hash)?;
+        let threshold = self.blocks[selected_parent]
+            .weight
+            .saturating_sub(self.params.merge_depth);
+
+        let mut current = selected_parent;
+        while self.blocks[current].weight > threshold {
+            match self.selected_parent(current) {
+                Some(parent) => current = parent,
+                None => break,
+            }
+        }
+        Some(current.to_string())
+    }
+
+    /// Remove a block and its links from the DAG, without touching its children
+    fn remove_block(&mut self, hash: &str) -> Option<Block> {
+        let block = self.blocks.remove(hash)?;
+        self.children_mapping.remove(hash);
+        for parent in &block.parent_hashes {
+            if let Some(children) = self.children_mapping.get_mut(parent) {
+                children.remove(hash);
+            }
+        }
+        Some(block)
+    }
+
+    /// Get the virtual blue score: the number of blue blocks in the DAG, pruned ones included
+    pub fn virtual_blue_score(&self) -> u64 {
+        self.blocks.values().map(|b| b.weight).max().unwrap_or(0)
+    }
+
+    /// Get the finality point: the blue block `finality_depth` below the virtual blue score
+    pub fn finality_point(&self) -> Option<&Block> {
+        let score = self
+            .virtual_blue_score()
+            .checked_sub(self.params.finality_depth)?;
+        self.blocks
+            .values()
+            .find(|b| b.color == BlockColor::Blue && b.weight == score)
+    }
+
+    /// Get the current pruning point
+    pub fn pruning_point(&self) -> &str {
+        &self.pruning_point
+    }
+
+    /// Get the commitment to the account state at the pruning point
+    pub fn pruning_point_commitment(&self) -> u64 {
+        self.pruning_point_commitment
+    }
+
+    /// Get the header of a block, including pruned ones
+    pub fn get_header(&self, hash: &str) -> Option<BlockHeader> {
+        self.blocks
+            .get(hash)
+            .map(|b| b.header())
+            .or_else(|| self.pruned_headers.get(hash).cloned())
+    }
+
+    /// Commitment to the current account state
+    pub fn state_commitment(&self) -> u64 {
+        commitment_of(&self.accounts)
+    }
+
+    /// Move the pruning point to a finalized blue block
+    ///
+    /// Every block that is not the new pruning point or in its future is discarded:
+    /// its body and GHOSTDAG data are dropped and only its header is kept. Blocks
+    /// that reference pruned blocks are rejected afterwards. The account state at the
+    /// new pruning point, after its selected chain up to and including it is executed,
+    /// becomes the state later executions start from. The receipts of the pruning point
+    /// are kept, since later executions do not run it again.
+    pub fn prune(&mut self, new_pruning_point: &str) -> Result<(), String> {
+        let point = self
+            .blocks
+            .get(new_pruning_point)
+            .ok_or_else(|| format!("Block '{}' does not exist", new_pruning_point))?;
+        if point.color != BlockColor::Blue {
+            return Err(format!("Pruning point '{}' is not blue", new_pruning_point));
+        }
+        if point.weight + self.params.finality_depth > self.virtual_blue_score() {
+            return Err(format!(
+                "Pruning point '{}' is not finalized (finality depth {})",
+                new_pruning_point, self.params.finality_depth
+            ));
+        }
+        let pruned_blue_count = point.weight - 1;
+
+        // The old pruning point is already part of the state, the new one is executed
+        // last so its receipts are recorded
+        let chain = self.chain_to(new_pruning_point);
+        let mut accounts = self.pruning_point_accounts.clone();
+        if let Some((_, between)) = chain[1..].split_last() {
+            for chain_block in between {
+                for block_hash in self.accepted_blocks(chain_block) {
+                    for tx in &self.blocks[&block_hash].transactions {
+                        let _ = Self::execute_transaction(&mut accounts, &mut tx.clone());
+                    }
+                }
+            }
+            std::mem::swap(&mut self.accounts, &mut accounts);
+            self.execute_chain_block(new_pruning_point);
+            std::mem::swap(&mut self.accounts, &mut accounts);
+        }
+
+        let mut retained = self.get_descendants(new_pruning_point);
+        retained.insert(new_pruning_point.to_string());
+        let pruned: Vec<String> = self
+            .blocks
+            .keys()
+            .filter(|h| !retained.contains(*h))
+            .cloned()
+            .collect();
+
+        for hash in pruned {
+            if let Some(block) = self.remove_block(&hash) {
+                if let Some(index) = &mut self.tx_index {
+                    index.remove_block(&block);
+                }
+                self.receipts.remove(&hash);
+                self.acceptance.remove(&hash);
+                self.pruned_headers.insert(hash, block.header());
+            }
+        }
+        self.receipt_locations
+            .retain(|_, chain_block| retained.contains(chain_block));
+
+        self.pruning_point = new_pruning_point.to_string();
+        self.pruned_blue_count = pruned_blue_count;
+        self.pruning_point_commitment = commitment_of(&accounts);
+        self.pruning_point_accounts = accounts;
+
+        self.update_ghostdag_ordering();
+        Ok(())
+    }
+
+    /// Check every block descends from the pruning point and there are no cycles
+    fn is_rooted_at_pruning_point(&self) -> bool {
+        let mut in_degree: HashMap<&str, usize> = HashMap::new();
+        for block in self.blocks.values() {
+            let parents = block
+                .parent_hashes
+                .iter()
+                .filter(|p| self.blocks.contains_key(*p))
+                .count();
+            in_degree.insert(&block.hash, parents);
+        }
+        if in_degree.get(self.pruning_point.as_str()) != Some(&0) {
+            return false;
+        }
+
+        let mut queue = VecDeque::from([self.pruning_point.as_str()]);
+        let mut visited = 0;
+        while let Some(hash) = queue.pop_front() {
+            visited += 1;
+            for child in &self.children_mapping[hash] {
+                let degree = in_degree.get_mut(child.as_str()).unwrap();
+                *degree -= 1;
+                if *degree == 0 {
+                    queue.push_back(child);
+                }
+            }
+        }
+        visited == self.blocks.len()
+    }
+
     /// GHOSTDAG algorithm: calculate blue block set and ordering
-    fn update_ghostdag_ordering(&mut self) -> Result<(), String> {
-        let ordered_blocks = self.ghostdag_sort()?;
+    fn update_ghostdag_ordering(&mut self) {
+        let ordered_blocks = self.ghostdag_sort();
 
         // Reset all block colors and weights
         for block in self.blocks.values_mut() {
@@ -180,7 +906,7 @@ impl BlockDAG {
         }
 
         // Mark blue blocks and calculate weights in GHOSTDAG order
-        let mut weight = 0u64;
+        let mut weight = self.pruned_blue_count;
         for hash in ordered_blocks {
             if let Some(block) = self.blocks.get_mut(&hash) {
                 block.color = BlockColor::Blue;
@@ -188,19 +914,22 @@ impl BlockDAG {
                 block.weight = weight;
             }
         }
-
-        Ok(())
     }
 
     /// GHOSTDAG topological sort
-    fn ghostdag_sort(&self) -> Result<Vec<String>, String> {
+    fn ghostdag_sort(&self) -> Vec<String> {
         let mut blue_set = HashSet::new();
         let mut ordered = Vec::new();
 
-        // Calculate in-degrees (number of parents) for all blocks
+        // Calculate in-degrees (number of parents) for all blocks, pruned parents excluded
         let mut in_degree = HashMap::new();
         for block in self.blocks.values() {
-            in_degree.insert(block.hash.clone(), block.parent_hashes.len());
+            let parents = block
+                .parent_hashes
+                .iter()
+                .filter(|p| self.blocks.contains_key(*p))
+                .count();
+            in_degree.insert(block.hash.clone(), parents);
         }
 
         // Priority queue for topological sort
@@ -210,8 +939,8 @@ impl BlockDAG {
         // 2. Earlier timestamp (tie-breaker)
         let mut heap = BinaryHeap::new();
 
-        // Initialize heap with blocks that have 0 in-degree (genesis)
-        if let Some(genesis) = self.blocks.get("genesis") {
+        // Initialize heap with the root of the DAG (genesis, or the pruning point once pruned)
+        if let Some(genesis) = self.blocks.get(&self.pruning_point) {
             let descendant_count = self.get_descendants(&genesis.hash).len();
             heap.push((
                 descendant_count,
@@ -222,8 +951,8 @@ impl BlockDAG {
 
         while let Some((_, _, current_hash)) = heap.pop() {
             // Determine if block is blue
-            // Genesis is always blue
-            let is_blue = if current_hash == "genesis" {
+            // The root is always blue
+            let is_blue = if current_hash == self.pruning_point {
                 true
             } else {
                 self.is_blue_candidate(&current_hash, &blue_set)
@@ -255,7 +984,7 @@ impl BlockDAG {
             }
         }
 
-        Ok(ordered)
+        ordered
     }
 
     /// Determine if candidate block should be blue
@@ -273,7 +1002,7 @@ impl BlockDAG {
             .count();
 
         // If blue anticone size doesn't exceed k, block can be blue
-        blue_anticone_size <= self.k
+        blue_anticone_size <= self.params.k
     }
 
     /// Get anticone (blocks that are neither ancestors nor descendants)
@@ -353,13 +1082,21 @@ impl BlockDAG {
         blue_blocks
     }
 
-    /// Execute transaction
-    fn execute_transaction(&mut self, tx: &mut Transaction) -> Result<(), String> {
+    /// Execute transaction on an account state, returning the balance change of every
+    /// touched account
+    fn execute_transaction(
+        accounts: &mut HashMap<String, Account>,
+        tx: &mut Transaction,
+    ) -> Result<BTreeMap<String, i128>, String> {
         // Check sender account
-        let sender = self
-            .accounts
-            .get_mut(&tx.from)
-            .ok_or_else(|| format!("Sender account '{}' does not exist", tx.from))?;
+        let sender = match accounts.get_mut(&tx.from) {
+            Some(sender) => sender,
+            None => {
+                tx.status =
+                    TxStatus::Failed(format!("Sender account '{}' does not exist", tx.from));
+                return Err(tx.status.clone().to_string());
+            }
+        };
 
         // Verify nonce
         if tx.nonce != sender.nonce {
@@ -371,62 +1108,249 @@ impl BlockDAG {
         }
 
         // Verify balance
-        if sender.balance < tx.amount {
+        let total = tx.amount.saturating_add(tx.fee);
+        if sender.balance < total {
             tx.status = TxStatus::Failed(format!(
                 "Insufficient balance: has {}, needs {}",
-                sender.balance, tx.amount
+                sender.balance, total
             ));
             return Err(tx.status.clone().to_string());
         }
 
-        // Execute transfer
-        sender.balance -= tx.amount;
+        // Execute transfer, the fee is burned
+        sender.balance -= total;
         sender.nonce += 1;
 
         // Receiver account
-        if let Some(receiver) = self.accounts.get_mut(&tx.to) {
+        if let Some(receiver) = accounts.get_mut(&tx.to) {
             receiver.balance += tx.amount;
         } else {
             // If receiver doesn't exist, create new account
-            self.accounts
-                .insert(tx.to.clone(), Account::new(tx.to.clone(), tx.amount));
+            accounts.insert(tx.to.clone(), Account::new(tx.to.clone(), tx.amount));
         }
 
+        let mut deltas = BTreeMap::new();
+        *deltas.entry(tx.from.clone()).or_insert(0) -= total as i128;
+        *deltas.entry(tx.to.clone()).or_insert(0) += tx.amount as i128;
+
         tx.status = TxStatus::Executed;
-        Ok(())
+        Ok(deltas)
     }
 
-    /// Execute all transactions in blue blocks
-    pub fn execute_blue_chain(&mut self) -> Result<(), String> {
-        let blue_blocks = self.get_ordered_blue_blocks();
-        let block_hashes: Vec<String> = blue_blocks.iter().map(|b| b.hash.clone()).collect();
+    /// Get the selected chain, from the pruning point to the virtual's selected parent
+    ///
+    /// The chain starts at the tip the virtual block would select (the highest weight
+    /// tip) and follows selected parents back down to the pruning point.
+    pub fn selected_chain(&self) -> Vec<String> {
+        let tips: Vec<String> = self
+            .children_mapping
+            .iter()
+            .filter(|(_, children)| children.is_empty())
+            .map(|(hash, _)| hash.clone())
+            .collect();
+        match self.selected_parent_of(&tips) {
+            Some(tip) => self.chain_to(tip),
+            None => Vec::new(),
+        }
+    }
 
-        for hash in block_hashes {
-            // Clone transaction list first
-            let transactions = if let Some(block) = self.blocks.get(&hash) {
-                block.transactions.clone()
-            } else {
+    /// Selected chain from the pruning point up to and including a block
+    fn chain_to(&self, hash: &str) -> Vec<String> {
+        let mut chain = vec![hash.to_string()];
+        let mut current = hash;
+        while let Some(parent) = self.selected_parent(current) {
+            chain.push(parent.to_string());
+            current = parent;
+        }
+        chain.reverse();
+        chain
+    }
+
+    /// Blocks whose transactions a chain block accepts, in execution order: its
+    /// mergeset, blue and red, in topological order (ties broken by timestamp), then
+    /// the chain block itself
+    fn accepted_blocks(&self, chain_block: &str) -> Vec<String> {
+        // A block's past is larger than any of its ancestors', so parents come first
+        let mut merged: Vec<(usize, &Block)> = self
+            .mergeset(chain_block)
+            .iter()
+            .filter_map(|h| self.blocks.get(h))
+            .map(|b| (self.get_ancestors(&b.hash).len(), b))
+            .collect();
+        merged.sort_by(|(a_past, a), (b_past, b)| {
+            a_past
+                .cmp(b_past)
+                .then_with(|| a.timestamp.cmp(&b.timestamp))
+                .then_with(|| a.hash.cmp(&b.hash))
+        });
+        let mut accepted: Vec<String> = merged.iter().map(|(_, b)| b.hash.clone()).collect();
+        accepted.push(chain_block.to_string());
+        accepted
+    }
+
+    /// Execute all transactions accepted by the selected chain
+    ///
+    /// Execution starts over from the account state at the pruning point, which
+    /// already includes the transactions the pruning point accepted, so its receipts
+    /// are kept. Chain blocks above it are processed in order, each one accepting the
+    /// blocks of its mergeset, then its own transactions.
+    pub fn execute_blue_chain(&mut self) -> Result<(), String> {
+        let chain = self.selected_chain();
+        let pruning_point = self.pruning_point.clone();
+
+        self.accounts = self.pruning_point_accounts.clone();
+        self.receipts.retain(|hash, _| *hash == pruning_point);
+        self.receipt_locations
+            .retain(|_, chain_block| *chain_block == pruning_point);
+        self.acceptance.retain(|hash, _| *hash == pruning_point);
+        if let Some(index) = &mut self.tx_index {
+            index.reset_acceptance(&pruning_point);
+        }
+        for block in self.blocks.values_mut() {
+            if block.hash == pruning_point {
                 continue;
-            };
+            }
+            for tx in &mut block.transactions {
+                tx.status = TxStatus::Pending;
+            }
+        }
+
+        for hash in chain.iter().skip(1) {
+            self.execute_chain_block(hash);
+        }
+
+        Ok(())
+    }
+
+    /// Execute the blocks a chain block accepts and record its receipts and acceptance data
+    fn execute_chain_block(&mut self, hash: &str) {
+        let mut receipts = Vec::new();
+        let mut acceptance = Vec::new();
+        for block_hash in self.accepted_blocks(hash) {
+            let block_receipts = self.execute_block_transactions(&block_hash, hash);
+            acceptance.push(MergedBlockAcceptance {
+                block_hash,
+                accepted_tx_ids: block_receipts
+                    .iter()
+                    .filter(|r| r.is_accepted())
+                    .map(|r| r.tx_id.clone())
+                    .collect(),
+            });
+            receipts.extend(block_receipts);
+        }
+
+        self.store_receipts(hash, receipts);
+        self.acceptance.insert(hash.to_string(), acceptance);
+    }
+
+    /// Execute the transactions of a block on behalf of the accepting chain block
+    fn execute_block_transactions(
+        &mut self,
+        block_hash: &str,
+        chain_block: &str,
+    ) -> Vec<TxReceipt> {
+        // Clone transaction list first
+        let transactions = match self.blocks.get(block_hash) {
+            Some(block) => block.transactions.clone(),
+            None => return Vec::new(),
+        };
 
-            // Execute transactions and collect results
-            let mut results = Vec::new();
-            for mut tx in transactions {
-                let result = self.execute_transaction(&mut tx);
-                results.push((tx, result));
+        // Execute transactions and collect results
+        let mut results = Vec::new();
+        let mut receipts = Vec::new();
+        for mut tx in transactions {
+            let result = Self::execute_transaction(&mut self.accounts, &mut tx);
+            receipts.push(TxReceipt {
+                tx_id: tx.id.clone(),
+                block_hash: block_hash.to_string(),
+                accepting_block: chain_block.to_string(),
+                status: tx.status.clone(),
+                fee: if result.is_ok() { tx.fee } else { 0 },
+                balance_deltas: result.clone().unwrap_or_default(),
+            });
+            if let Some(index) = &mut self.tx_index {
+                index.record_receipt(&tx.from, &tx.to, receipts.last().unwrap());
             }
+            results.push((tx, result));
+        }
 
-            // Update transaction status in block
-            if let Some(block) = self.blocks.get_mut(&hash) {
-                for (i, (tx, _)) in results.iter().enumerate() {
-                    if i < block.transactions.len() {
-                        block.transactions[i] = tx.clone();
-                    }
+        // Update transaction status in block
+        if let Some(block) = self.blocks.get_mut(block_hash) {
+            for (i, (tx, _)) in results.iter().enumerate() {
+                if i < block.transactions.len() {
+                    block.transactions[i] = tx.clone();
                 }
             }
         }
 
-        Ok(())
+        receipts
+    }
+
+    /// Get the acceptance data of a chain block: the blocks it merged and their accepted txs
+    pub fn acceptance_data(&self, chain_block: &str) -> Option<&[MergedBlockAcceptance]> {
+        self.acceptance.get(chain_block).map(|a| a.as_slice())
+    }
+
+    /// Get the chain block that accepted a transaction
+    pub fn accepting_block(&self, tx_id: &str) -> Option<&str> {
+        self.receipt(tx_id)
+            .filter(|r| r.is_accepted())
+            .map(|r| r.accepting_block.as_str())
+    }
+
+    /// Get the number of confirmations of an accepted transaction, measured in blue score
+    ///
+    /// The accepting block counts as the first confirmation. Returns `None` if the
+    /// transaction is not accepted or its accepting block is no longer blue.
+    pub fn confirmations(&self, tx_id: &str) -> Option<u64> {
+        let accepting = self.blocks.get(self.accepting_block(tx_id)?)?;
+        if accepting.color != BlockColor::Blue {
+            return None;
+        }
+        Some(self.virtual_blue_score() - accepting.weight + 1)
+    }
+
+    /// Replace the receipts recorded by a chain block
+    ///
+    /// A rejected duplicate of a transaction never hides the receipt of its accepted copy.
+    fn store_receipts(&mut self, chain_block: &str, receipts: Vec<TxReceipt>) {
+        for receipt in &receipts {
+            let accepted_elsewhere = self
+                .receipt(&receipt.tx_id)
+                .is_some_and(|r| r.is_accepted());
+            if receipt.is_accepted() || !accepted_elsewhere {
+                self.receipt_locations
+                    .insert(receipt.tx_id.clone(), chain_block.to_string());
+            }
+        }
+        self.receipts.insert(chain_block.to_string(), receipts);
+    }
+
+    /// Get the latest receipt of a transaction, the accepted one if a copy was accepted
+    pub fn receipt(&self, tx_id: &str) -> Option<&TxReceipt> {
+        let chain_block = self.receipt_locations.get(tx_id)?;
+        self.receipt_in(chain_block, tx_id)
+    }
+
+    /// Get the receipt a specific chain block recorded for a transaction
+    ///
+    /// If the chain block accepted several copies, the accepted one is returned.
+    pub fn receipt_in(&self, chain_block: &str, tx_id: &str) -> Option<&TxReceipt> {
+        let mut copies = self
+            .receipts
+            .get(chain_block)?
+            .iter()
+            .filter(|r| r.tx_id == tx_id);
+        let first = copies.next()?;
+        Some(copies.find(|r| r.is_accepted()).unwrap_or(first))
+    }
+
+    /// Get all receipts recorded by a chain block, in execution order
+    pub fn block_receipts(&self, chain_block: &str) -> &[TxReceipt] {
+        self.receipts
+            .get(chain_block)
+            .map(|r| r.as_slice())
+            .unwrap_or(&[])
     }
 
     /// Revert transaction
@@ -435,9 +1359,9 @@ impl BlockDAG {
             return Err("Transaction was not executed".to_string());
         }
 
-        // Restore sender balance and nonce
+        // Restore sender balance (including the burned fee) and nonce
         if let Some(sender) = self.accounts.get_mut(&tx.from) {
-            sender.balance += tx.amount;
+            sender.balance += tx.amount.saturating_add(tx.fee);
             sender.nonce = sender.nonce.saturating_sub(1);
         }
 
@@ -473,9 +1397,27 @@ impl BlockDAG {
             reverted_txs.push((i, tx));
         }
 
-        // Update transaction status in block
+        // Update transaction status in block and in its receipts
         let block = self.blocks.get_mut(block_hash).unwrap();
         for (i, tx) in reverted_txs {
+            if let Some(chain_block) = self.receipt_locations.get(&tx.id)
+                && let Some(receipt) = self.receipts.get_mut(chain_block).and_then(|r| {
+                    r.iter_mut()
+                        .find(|r| r.tx_id == tx.id && r.block_hash == block_hash)
+                })
+            {
+                receipt.status = TxStatus::Reverted;
+                if let Some(index) = &mut self.tx_index {
+                    index.record_receipt(&tx.from, &tx.to, receipt);
+                }
+                if let Some(merged) = self
+                    .acceptance
+                    .get_mut(&receipt.accepting_block)
+                    .and_then(|a| a.iter_mut().find(|m| m.block_hash == block_hash))
+                {
+                    merged.accepted_tx_ids.retain(|id| *id != tx.id);
+                }
+            }
             block.transactions[i] = tx;
         }
 
@@ -491,6 +1433,285 @@ impl BlockDAG {
     pub fn get_all_blocks(&self) -> Vec<&Block> {
         self.blocks.values().collect()
     }
+
+    /// Persist headers, bodies, GHOSTDAG data, account state and pruning data
+    ///
+    /// Only changed records are written, in a single atomic batch. Execution
+    /// receipts and the transaction index are not persisted. Nothing is written if
+    /// a record exceeds the limits `load` accepts.
+    pub fn save(&self, store: &mut dyn Store) -> Result<(), String> {
+        let mut records: BTreeMap<String, Vec<u8>> = BTreeMap::new();
+
+        records.insert(
+            "meta/genesis".to_string(),
+            storage::encode_u64(self.params.genesis.commitment()),
+        );
+        records.insert(
+            "meta/pruning".to_string(),
+            storage::encode_pruning(
+                &self.pruning_point,
+                self.pruned_blue_count,
+                self.pruning_point_commitment,
+            )
+            .map_err(|e| format!("Cannot save pruning point: {}", e))?,
+        );
+
+        let unencodable = |hash: &str, e| format!("Cannot save block '{}': {}", hash, e);
+        for (hash, header) in &self.pruned_headers {
+            records.insert(
+                format!("header/{}", hash),
+                storage::encode_header(header).map_err(|e| unencodable(hash, e))?,
+            );
+        }
+        for (hash, block) in &self.blocks {
+            records.insert(
+                format!("header/{}", hash),
+                storage::encode_header(&block.header()).map_err(|e| unencodable(hash, e))?,
+            );
+            records.insert(
+                format!("body/{}", hash),
+                storage::encode_body(&block.transactions).map_err(|e| unencodable(hash, e))?,
+            );
+            records.insert(
+                format!("ghostdag/{}", hash),
+                storage::encode_ghostdag(&block.color, block.weight),
+            );
+        }
+        for (address, account) in &self.accounts {
+            records.insert(
+                format!("account/{}", address),
+                storage::encode_account(account),
+            );
+        }
+        for (address, account) in &self.pruning_point_accounts {
+            records.insert(
+                format!("pruning_account/{}", address),
+                storage::encode_account(account),
+            );
+        }
+
+        let mut batch = WriteBatch::new();
+        for prefix in [
+            "meta/",
+            "header/",
+            "body/",
+            "ghostdag/",
+            "account/",
+            "pruning_account/",
+        ] {
+            for (key, _) in store.scan_prefix(prefix)? {
+                if !records.contains_key(&key) {
+                    batch.delete(key);
+                }
+            }
+        }
+        for (key, value) in records {
+            if store.get(&key)?.as_ref() != Some(&value) {
+                batch.put(key, value);
+            }
+        }
+        store.write(batch)
+    }
+
+    /// Reopen a BlockDAG saved with `save`
+    ///
+    /// The store must have been written for the same genesis. Children links are
+    /// rebuilt from the parents recorded in the headers.
+    pub fn load(params: ConsensusParams, store: &dyn Store) -> Result<Self, String> {
+        let genesis = store
+            .get("meta/genesis")?
+            .ok_or_else(|| "Store does not contain a BlockDAG".to_string())?;
+        if storage::decode_u64("meta/genesis", &genesis)? != params.genesis.commitment() {
+            return Err("Store was written for a different genesis".to_string());
+        }
+
+        let pruning = store
+            .get("meta/pruning")?
+            .ok_or_else(|| "Missing pruning data".to_string())?;
+        let (pruning_point, pruned_blue_count, pruning_point_commitment) =
+            storage::decode_pruning("meta/pruning", &pruning)?;
+
+        let mut blocks = Vec::new();
+        let mut pruned_headers = Vec::new();
+        for (key, value) in store.scan_prefix("header/")? {
+            let header = storage::decode_header(&key, &value)?;
+            if header.hash != key["header/".len()..] {
+                return Err(format!("Header stored under wrong key '{}'", key));
+            }
+            let (Some(body), Some(ghostdag)) = (
+                store.get(&format!("body/{}", header.hash))?,
+                store.get(&format!("ghostdag/{}", header.hash))?,
+            ) else {
+                pruned_headers.push(header);
+                continue;
+            };
+            let (color, weight) = storage::decode_ghostdag(&key, &ghostdag)?;
+            blocks.push(Block {
+                hash: header.hash,
+                parent_hashes: header.parent_hashes,
+                transactions: storage::decode_body(&key, &body)?,
+                timestamp: header.timestamp,
+                height: header.height,
+                color,
+                weight,
+            });
+        }
+
+        let mut accounts = Vec::new();
+        for (key, value) in store.scan_prefix("account/")? {
+            let address = &key["account/".len()..];
+            accounts.push(storage::decode_account(&key, address, &value)?);
+        }
+        let mut pruning_point_accounts = Vec::new();
+        for (key, value) in store.scan_prefix("pruning_account/")? {
+            let address = &key["pruning_account/".len()..];
+            pruning_point_accounts.push(storage::decode_account(&key, address, &value)?);
+        }
+
+        Self::restore(BlockDagSnapshot {
+            params,
+            blocks,
+            pruned_headers,
+            accounts,
+            pruning_point,
+            pruned_blue_count,
+            pruning_point_accounts,
+            pruning_point_commitment,
+        })
+    }
+
+    /// Export the whole DAG
+    pub fn snapshot(&self) -> BlockDagSnapshot {
+        let mut blocks: Vec<Block> = self.blocks.values().cloned().collect();
+        blocks.sort_by(|a, b| {
+            a.timestamp
+                .cmp(&b.timestamp)
+                .then_with(|| a.hash.cmp(&b.hash))
+        });
+        let mut pruned_headers: Vec<BlockHeader> = self.pruned_headers.values().cloned().collect();
+        pruned_headers.sort_by(|a, b| a.hash.cmp(&b.hash));
+        let mut accounts: Vec<Account> = self.accounts.values().cloned().collect();
+        accounts.sort_by(|a, b| a.address.cmp(&b.address));
+        let mut pruning_point_accounts: Vec<Account> =
+            self.pruning_point_accounts.values().cloned().collect();
+        pruning_point_accounts.sort_by(|a, b| a.address.cmp(&b.address));
+
+        BlockDagSnapshot {
+            params: self.params.clone(),
+            blocks,
+            pruned_headers,
+            accounts,
+            pruning_point: self.pruning_point.clone(),
+            pruned_blue_count: self.pruned_blue_count,
+            pruning_point_accounts,
+            pruning_point_commitment: self.pruning_point_commitment,
+        }
+    }
+
+    /// Import a DAG exported with `snapshot`
+    ///
+    /// The block structure is validated and GHOSTDAG colors and weights are recomputed,
+    /// so the ones recorded in the snapshot are ignored.
+    pub fn from_snapshot(snapshot: BlockDagSnapshot) -> Result<Self, String> {
+        let mut dag = Self::restore(snapshot)?;
+        dag.update_ghostdag_ordering();
+        Ok(dag)
+    }
+
+    /// Rebuild a DAG from exported parts, keeping the recorded GHOSTDAG data
+    fn restore(snapshot: BlockDagSnapshot) -> Result<Self, String> {
+        let mut dag = Self::from_params(snapshot.params);
+        dag.blocks.clear();
+        dag.children_mapping.clear();
+        dag.pruning_point = snapshot.pruning_point;
+        dag.pruned_blue_count = snapshot.pruned_blue_count;
+        dag.pruning_point_commitment = snapshot.pruning_point_commitment;
+
+        for header in snapshot.pruned_headers {
+            dag.pruned_headers.insert(header.hash.clone(), header);
+        }
+        for block in snapshot.blocks {
+            if dag.blocks.contains_key(&block.hash) || dag.pruned_headers.contains_key(&block.hash)
+            {
+                return Err(format!("Duplicate block '{}'", block.hash));
+            }
+            dag.children_mapping
+                .insert(block.hash.clone(), HashSet::new());
+            dag.blocks.insert(block.hash.clone(), block);
+        }
+        if !dag.blocks.contains_key(&dag.pruning_point) {
+            return Err(format!("Pruning point '{}' is missing", dag.pruning_point));
+        }
+        for block in dag.blocks.values() {
+            for parent in &block.parent_hashes {
+                if let Some(children) = dag.children_mapping.get_mut(parent) {
+                    children.insert(block.hash.clone());
+                } else if !dag.pruned_headers.contains_key(parent) {
+                    return Err(format!(
+                        "Block '{}' references unknown parent '{}'",
+                        block.hash, parent
+                    ));
+                }
+            }
+        }
+        if !dag.is_rooted_at_pruning_point() {
+            return Err("Blocks do not form a DAG rooted at the pruning point".to_string());
+        }
+
+        dag.accounts = snapshot
+            .accounts
+            .into_iter()
+            .map(|account| (account.address.clone(), account))
+            .collect();
+        dag.pruning_point_accounts = snapshot
+            .pruning_point_accounts
+            .into_iter()
+            .map(|account| (account.address.clone(), account))
+            .collect();
+        if commitment_of(&dag.pruning_point_accounts) != dag.pruning_point_commitment {
+            return Err("Pruning point accounts do not match their commitment".to_string());
+        }
+
+        Ok(dag)
+    }
+}
+
+/// Initial account state defined by a genesis
+fn genesis_accounts(genesis: &GenesisConfig) -> HashMap<String, Account> {
+    genesis
+        .allocations
+        .iter()
+        .map(|(address, balance)| (address.clone(), Account::new(address.clone(), *balance)))
+        .collect()
+}
+
+/// Commitment to an account state (FNV-1a over accounts sorted by address)
+fn commitment_of(accounts: &HashMap<String, Account>) -> u64 {
+    let mut accounts: Vec<&Account> = accounts.values().collect();
+    accounts.sort_by(|a, b| a.address.cmp(&b.address));
+
+    let mut hasher = Fnv1a::new();
+    for account in accounts {
+        hasher.write_str(&account.address);
+        hasher.write_u64(account.balance);
+        hasher.write_u64(account.nonce);
+    }
+    hasher.finish()
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for BlockDAG {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.snapshot().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for BlockDAG {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let snapshot = BlockDagSnapshot::deserialize(deserializer)?;
+        BlockDAG::from_snapshot(snapshot).map_err(serde::de::Error::custom)
+    }
 }
 
 impl fmt::Display for TxStatus {
@@ -0,0 +1,474 @@
+use std::fmt;
+
+use super::{Block, BlockHeader, Transaction};
+
+/// Version byte prefixed to every encoded message
+pub const CODEC_VERSION: u8 = 1;
+
+/// Maximum size of an encoded message, version byte included
+pub const MAX_MESSAGE_SIZE: usize = 4 * 1024 * 1024;
+
+/// Maximum length of an encoded string (hashes, ids, addresses), in bytes
+pub const MAX_STRING_LEN: usize = 1024;
+
+/// Maximum number of parents of an encoded header
+pub const MAX_ENCODED_PARENTS: usize = 255;
+
+/// Maximum number of transactions of an encoded block
+pub const MAX_ENCODED_TRANSACTIONS: usize = 65_536;
+
+/// Reason a value could not be encoded or a message could not be decoded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CodecError {
+    UnexpectedEnd,
+    TrailingBytes(usize),
+    UnsupportedVersion(u8),
+    TooLarge {
+        what: &'static str,
+        size: usize,
+        limit: usize,
+    },
+    InvalidUtf8,
+    InvalidTag {
+        what: &'static str,
+        tag: u8,
+    },
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodecError::UnexpectedEnd => write!(f, "Unexpected end of data"),
+            CodecError::TrailingBytes(count) => write!(f, "{} trailing bytes", count),
+            CodecError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported encoding version {}", version)
+            }
+            CodecError::TooLarge { what, size, limit } => {
+                write!(
+                    f,
+                    "{} of size {} exceeds the limit of {}",
+                    what, size, limit
+                )
+            }
+            CodecError::InvalidUtf8 => write!(f, "Invalid UTF-8 string"),
+            CodecError::InvalidTag { what, tag } => write!(f, "Invalid {} tag {}", what, tag),
+        }
+    }
+}
+
+impl std::error::Error for CodecError {}
+
+/// Deterministic binary encoding: little-endian integers, `u32` length prefixes
+///
+/// Encoding enforces the limits decoding applies, so every encoded message decodes.
+pub trait Encode {
+    /// Write the value, without the version byte
+    fn encode_to(&self, writer: &mut Writer) -> Result<(), CodecError>;
+
+    /// Encode the value as a versioned message
+    fn encode(&self) -> Result<Vec<u8>, CodecError> {
+        let mut writer = Writer::new();
+        writer.u8(CODEC_VERSION);
+        self.encode_to(&mut writer)?;
+        let bytes = writer.finish();
+        if bytes.len() > MAX_MESSAGE_SIZE {
+            return Err(CodecError::TooLarge {
+                what: "message",
+                size: bytes.len(),
+                limit: MAX_MESSAGE_SIZE,
+            });
+        }
+        Ok(bytes)
+    }
+}
+
+/// Decoding of values written by `Encode`
+pub trait Decode: Sized {
+    /// Read the value, without the version byte
+    fn decode_from(reader: &mut Reader<'_>) -> Result<Self, CodecError>;
+
+    /// Decode a versioned message, rejecting oversized input and trailing bytes
+    fn decode(bytes: &[u8]) -> Result<Self, CodecError> {
+        if bytes.len() > MAX_MESSAGE_SIZE {
+            return Err(CodecError::TooLarge {
+                what: "message",
+                size: bytes.len(),
+                limit: MAX_MESSAGE_SIZE,
+            });
+        }
+        let mut reader = Reader::new(bytes);
+        let version = reader.u8()?;
+        if version != CODEC_VERSION {
+            return Err(CodecError::UnsupportedVersion(version));
+        }
+        let value = Self::decode_from(&mut reader)?;
+        reader.finish()?;
+        Ok(value)
+    }
+}
+
+/// Encoder of primitive values
+#[derive(Debug, Default)]
+pub struct Writer(Vec<u8>);
+
+impl Writer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn u8(&mut self, value: u8) {
+        self.0.push(value);
+    }
+
+    pub fn u32(&mut self, value: u32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn u64(&mut self, value: u64) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// Write a collection length or byte length, bounded by `limit` like `Reader::len`
+    pub fn len(&mut self, what: &'static str, len: usize, limit: usize) -> Result<(), CodecError> {
+        let limit = limit.min(u32::MAX as usize);
+        if len > limit {
+            return Err(CodecError::TooLarge {
+                what,
+                size: len,
+                limit,
+            });
+        }
+        self.u32(len as u32);
+        Ok(())
+    }
+
+    pub fn bytes(
+        &mut self,
+        what: &'static str,
+        value: &[u8],
+        limit: usize,
+    ) -> Result<(), CodecError> {
+        self.len(what, value.len(), limit)?;
+        self.0.extend_from_slice(value);
+        Ok(())
+    }
+
+    /// Write a string of at most `MAX_STRING_LEN` bytes
+    pub fn str(&mut self, what: &'static str, value: &str) -> Result<(), CodecError> {
+        self.bytes(what, value.as_bytes(), MAX_STRING_LEN)
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+/// Decoder of primitive values
+#[derive(Debug)]
+pub struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], CodecError> {
+        if self.buf.len() - self.pos < len {
+            return Err(CodecError::UnexpectedEnd);
+        }
+        let bytes = &self.buf[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    pub fn u8(&mut self) -> Result<u8, CodecError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn u32(&mut self) -> Result<u32, CodecError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    pub fn u64(&mut self) -> Result<u64, CodecError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    /// Read a length written by `Writer::len`, bounded by `limit`
+    pub fn len(&mut self, what: &'static str, limit: usize) -> Result<usize, CodecError> {
+        let size = self.u32()? as usize;
+        if size > limit {
+            return Err(CodecError::TooLarge { what, size, limit });
+        }
+        Ok(size)
+    }
+
+    pub fn bytes(&mut self, what: &'static str, limit: usize) -> Result<&'a [u8], CodecError> {
+        let len = self.len(what, limit)?;
+        self.take(len)
+    }
+
+    /// Read a string of at most `MAX_STRING_LEN` bytes
+    pub fn str(&mut self, what: &'static str) -> Result<String, CodecError> {
+        let bytes = self.bytes(what, MAX_STRING_LEN)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| CodecError::InvalidUtf8)
+    }
+
+    /// Number of bytes left to read
+    pub fn remaining(&self) -> usize {
+        self.buf.len() - self.pos
+    }
+
+    /// Check all input was consumed
+    pub fn finish(&self) -> Result<(), CodecError> {
+        match self.remaining() {
+            0 => Ok(()),
+            count => Err(CodecError::TrailingBytes(count)),
+        }
+    }
+}
+
+/// Wire form of a transaction: its signed content, without the local execution status
+impl Encode for Transaction {
+    fn encode_to(&self, writer: &mut Writer) -> Result<(), CodecError> {
+        writer.str("transaction id", &self.id)?;
+        writer.str("address", &self.from)?;
+        writer.str("address", &self.to)?;
+        writer.u64(self.amount);
+        writer.u64(self.fee);
+        writer.u64(self.nonce);
+        Ok(())
+    }
+}
+
+impl Decode for Transaction {
+    fn decode_from(reader: &mut Reader<'_>) -> Result<Self, CodecError> {
+        let id = reader.str("transaction id")?;
+        let from = reader.str("address")?;
+        let to = reader.str("address")?;
+        let amount = reader.u64()?;
+        let fee = reader.u64()?;
+        let nonce = reader.u64()?;
+        Ok(Transaction::new(id, from, to, amount, nonce).with_fee(fee))
+    }
+}
+
+impl Encode for BlockHeader {
+    fn encode_to(&self, writer: &mut Writer) -> Result<(), CodecError> {
+        writer.str("block hash", &self.hash)?;
+        writer.len("parent list", self.parent_hashes.len(), MAX_ENCODED_PARENTS)?;
+        for parent in &self.parent_hashes {
+            writer.str("block hash", parent)?;
+        }
+        writer.u64(self.timestamp);
+        writer.u64(self.height);
+        Ok(())
+    }
+}
+
+impl Decode for BlockHeader {
+    fn decode_from(reader: &mut Reader<'_>) -> Result<Self, CodecError> {
+        let hash = reader.str("block hash")?;
+        let parent_count = reader.len("parent list", MAX_ENCODED_PARENTS)?;
+        let mut parent_hashes = Vec::with_capacity(parent_count);
+        for _ in 0..parent_count {
+            parent_hashes.push(reader.str("block hash")?);
+        }
+        Ok(BlockHeader {
+            hash,
+            parent_hashes,
+            timestamp: reader.u64()?,
+            height: reader.u64()?,
+        })
+    }
+}
+
+/// Wire form of a block: header and transactions, without local GHOSTDAG data
+impl Encode for Block {
+    fn encode_to(&self, writer: &mut Writer) -> Result<(), CodecError> {
+        self.header().encode_to(writer)?;
+        writer.len(
+            "transaction list",
+            self.transactions.len(),
+            MAX_ENCODED_TRANSACTIONS,
+        )?;
+        for tx in &self.transactions {
+            tx.encode_to(writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl Decode for Block {
+    fn decode_from(reader: &mut Reader<'_>) -> Result<Self, CodecError> {
+        let header = BlockHeader::decode_from(reader)?;
+        let tx_count = reader.len("transaction list", MAX_ENCODED_TRANSACTIONS)?;
+        let mut transactions = Vec::with_capacity(tx_count.min(reader.remaining()));
+        for _ in 0..tx_count {
+            transactions.push(Transaction::decode_from(reader)?);
+        }
+        let mut block = Block::new(
+            header.hash,
+            header.parent_hashes,
+            transactions,
+            header.timestamp,
+        );
+        block.height = header.height;
+        Ok(block)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_block() -> Block {
+        let tx1 = Transaction::new(
+            "tx1".to_string(),
+            "alice".to_string(),
+            "bob".to_string(),
+            100,
+            0,
+        )
+        .with_fee(2);
+        let tx2 = Transaction::new(
+            "tx2".to_string(),
+            "bob".to_string(),
+            "carol".to_string(),
+            50,
+            0,
+        );
+        let mut block = Block::new(
+            "b1".to_string(),
+            vec!["genesis".to_string(), "b0".to_string()],
+            vec![tx1, tx2],
+            1_234,
+        );
+        block.height = 7;
+        block
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let block = sample_block();
+        let decoded = Block::decode(&block.encode().unwrap()).unwrap();
+        assert_eq!(decoded.header(), block.header());
+        assert_eq!(decoded.transactions, block.transactions);
+
+        let header = block.header();
+        assert_eq!(
+            BlockHeader::decode(&header.encode().unwrap()).unwrap(),
+            header
+        );
+
+        let tx = &block.transactions[0];
+        assert_eq!(&Transaction::decode(&tx.encode().unwrap()).unwrap(), tx);
+    }
+
+    #[test]
+    fn test_encoding_is_deterministic_and_little_endian() {
+        let tx = Transaction::new("t".to_string(), "a".to_string(), "b".to_string(), 1, 2);
+        let mut expected = vec![CODEC_VERSION];
+        for s in ["t", "a", "b"] {
+            expected.extend_from_slice(&1u32.to_le_bytes());
+            expected.extend_from_slice(s.as_bytes());
+        }
+        for value in [1u64, 0, 2] {
+            expected.extend_from_slice(&value.to_le_bytes());
+        }
+        assert_eq!(tx.encode().unwrap(), expected);
+        assert_eq!(sample_block().encode(), sample_block().encode());
+    }
+
+    #[test]
+    fn test_rejects_malformed_input() {
+        let bytes = sample_block().encode().unwrap();
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(
+            Block::decode(&trailing).unwrap_err(),
+            CodecError::TrailingBytes(1)
+        );
+
+        for len in 0..bytes.len() {
+            assert!(Block::decode(&bytes[..len]).is_err());
+        }
+
+        let mut wrong_version = bytes.clone();
+        wrong_version[0] = CODEC_VERSION + 1;
+        assert_eq!(
+            Block::decode(&wrong_version).unwrap_err(),
+            CodecError::UnsupportedVersion(CODEC_VERSION + 1)
+        );
+
+        // A huge declared parent count is rejected before allocating
+        let mut writer = Writer::new();
+        writer.u8(CODEC_VERSION);
+        writer.str("block hash", "b1").unwrap();
+        writer.u32(u32::MAX);
+        assert!(matches!(
+            BlockHeader::decode(&writer.finish()).unwrap_err(),
+            CodecError::TooLarge { .. }
+        ));
+
+        let mut writer = Writer::new();
+        writer.u8(CODEC_VERSION);
+        writer
+            .bytes("transaction id", &[0xff, 0xfe], MAX_STRING_LEN)
+            .unwrap();
+        assert_eq!(
+            Transaction::decode(&writer.finish()).unwrap_err(),
+            CodecError::InvalidUtf8
+        );
+
+        assert!(matches!(
+            Block::decode(&vec![0; MAX_MESSAGE_SIZE + 1]).unwrap_err(),
+            CodecError::TooLarge { .. }
+        ));
+    }
+
+    #[test]
+    fn test_rejects_values_beyond_decoding_limits() {
+        let mut tx = sample_block().transactions[0].clone();
+        tx.from = "a".repeat(MAX_STRING_LEN + 1);
+        assert_eq!(
+            tx.encode().unwrap_err(),
+            CodecError::TooLarge {
+                what: "address",
+                size: MAX_STRING_LEN + 1,
+                limit: MAX_STRING_LEN,
+            }
+        );
+
+        let mut block = sample_block();
+        block.parent_hashes = vec!["p".to_string(); MAX_ENCODED_PARENTS + 1];
+        assert!(matches!(
+            block.encode().unwrap_err(),
+            CodecError::TooLarge {
+                what: "parent list",
+                ..
+            }
+        ));
+
+        // Each transaction fits, but the message is too large to decode
+        let mut block = sample_block();
+        let tx = Transaction::new(
+            "t".repeat(MAX_STRING_LEN),
+            "a".repeat(MAX_STRING_LEN),
+            "b".repeat(MAX_STRING_LEN),
+            1,
+            0,
+        );
+        block.transactions = vec![tx; MAX_MESSAGE_SIZE / (3 * MAX_STRING_LEN) + 1];
+        assert!(matches!(
+            block.encode().unwrap_err(),
+            CodecError::TooLarge {
+                what: "message",
+                ..
+            }
+        ));
+    }
+}
@@ -0,0 +1,67 @@
+use std::collections::BTreeMap;
+
+use super::Block;
+use super::hashing::Fnv1a;
+
+/// Default genesis block hash
+pub const GENESIS_HASH: &str = "genesis";
+
+/// Genesis definition: the first block and the initial account state
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct GenesisConfig {
+    pub hash: String,
+    pub timestamp: u64,
+    pub allocations: BTreeMap<String, u64>, // Address -> initial balance
+    pub params_hash: u64, // Hash of the consensus parameters the genesis was sealed for
+}
+
+impl GenesisConfig {
+    pub fn new(timestamp: u64) -> Self {
+        Self {
+            timestamp,
+            ..Self::default()
+        }
+    }
+
+    /// Add an initial allocation
+    pub fn with_allocation(mut self, address: impl Into<String>, balance: u64) -> Self {
+        self.allocations.insert(address.into(), balance);
+        self
+    }
+
+    /// Produce the genesis block
+    pub fn block(&self) -> Block {
+        Block {
+            hash: self.hash.clone(),
+            timestamp: self.timestamp,
+            ..Block::genesis()
+        }
+    }
+
+    /// Deterministic commitment to the whole genesis definition
+    pub fn commitment(&self) -> u64 {
+        let mut hasher = Fnv1a::new();
+        hasher.write_str(&self.hash);
+        hasher.write_u64(self.timestamp);
+        hasher.write_u64(self.allocations.len() as u64);
+        for (address, balance) in &self.allocations {
+            hasher.write_str(address);
+            hasher.write_u64(*balance);
+        }
+        hasher.write_u64(self.params_hash);
+        hasher.finish()
+    }
+}
+
+impl Default for GenesisConfig {
+    fn default() -> Self {
+        Self {
+            hash: GENESIS_HASH.to_string(),
+            timestamp: 0,
+            allocations: BTreeMap::new(),
+            params_hash: 0,
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+
+use super::{Block, TxReceipt};
+
+/// Direction of a transaction relative to an indexed address
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxDirection {
+    Incoming,
+    Outgoing,
+}
+
+/// One entry in an address' transaction history
+///
+/// An address has a single entry per transaction, even when the transaction is a
+/// self-transfer or is included in several blocks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressTxEntry {
+    pub tx_id: String,
+    pub block_hash: String, // Block whose copy was accepted, else the first block including it
+    pub direction: TxDirection, // Outgoing for self-transfers
+    pub counterparty: String,
+    pub amount: u64,
+    pub accepting_block: Option<String>, // Set once a chain block accepts the transaction
+}
+
+/// Transaction index by id, address and block
+///
+/// Blocks are indexed as they are added to the DAG, acceptance is updated
+/// whenever the blue chain is executed or a block is reverted.
+#[derive(Debug, Default)]
+pub struct TxIndex {
+    tx_blocks: HashMap<String, Vec<String>>, // Tx id -> containing blocks
+    block_txs: HashMap<String, Vec<String>>, // Block hash -> tx ids
+    address_txs: HashMap<String, Vec<AddressTxEntry>>, // Address -> history in insertion order
+}
+
+impl TxIndex {
+    /// Create an empty index
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Index the transactions of a newly added block, ignoring blocks already indexed
+    pub(crate) fn index_block(&mut self, block: &Block) {
+        if self.block_txs.contains_key(&block.hash) {
+            return;
+        }
+        let mut tx_ids = Vec::with_capacity(block.transactions.len());
+        for tx in &block.transactions {
+            tx_ids.push(tx.id.clone());
+            let blocks = self.tx_blocks.entry(tx.id.clone()).or_default();
+            if !blocks.contains(&block.hash) {
+                blocks.push(block.hash.clone());
+            }
+
+            let entry = |direction, counterparty: &str| AddressTxEntry {
+                tx_id: tx.id.clone(),
+                block_hash: block.hash.clone(),
+                direction,
+                counterparty: counterparty.to_string(),
+                amount: tx.amount,
+                accepting_block: None,
+            };
+            for (address, direction, counterparty) in [
+                (&tx.from, TxDirection::Outgoing, &tx.to),
+                (&tx.to, TxDirection::Incoming, &tx.from),
+            ] {
+                let entries = self.address_txs.entry(address.clone()).or_default();
+                if !entries.iter().any(|e| e.tx_id == tx.id) {
+                    entries.push(entry(direction, counterparty));
+                }
+            }
+        }
+        self.block_txs.insert(block.hash.clone(), tx_ids);
+    }
+
+    /// Drop all entries of a block leaving the DAG
+    ///
+    /// History entries of transactions still included in another block move to it.
+    pub(crate) fn remove_block(&mut self, block: &Block) {
+        self.block_txs.remove(&block.hash);
+        for tx in &block.transactions {
+            let remaining = match self.tx_blocks.get_mut(&tx.id) {
+                Some(blocks) => {
+                    blocks.retain(|h| *h != block.hash);
+                    blocks.first().cloned()
+                }
+                None => None,
+            };
+            if remaining.is_none() {
+                self.tx_blocks.remove(&tx.id);
+            }
+            for address in [&tx.from, &tx.to] {
+                let Some(entries) = self.address_txs.get_mut(address) else {
+                    continue;
+                };
+                match &remaining {
+                    Some(other) => {
+                        for entry in entries
+                            .iter_mut()
+                            .filter(|e| e.tx_id == tx.id && e.block_hash == block.hash)
+                        {
+                            entry.block_hash = other.clone();
+                            entry.accepting_block = None;
+                        }
+                    }
+                    None => entries.retain(|e| e.tx_id != tx.id),
+                }
+            }
+        }
+    }
+
+    /// Forget the acceptance data before the chain is re-executed, except what the
+    /// `kept` chain block accepted
+    pub(crate) fn reset_acceptance(&mut self, kept: &str) {
+        for entry in self.address_txs.values_mut().flatten() {
+            if entry.accepting_block.as_deref() != Some(kept) {
+                entry.accepting_block = None;
+            }
+        }
+    }
+
+    /// Update the history entries of the transaction a receipt belongs to
+    ///
+    /// An accepted receipt points the entries at its block. A rejected or reverted one
+    /// only clears the acceptance recorded for the same block, so a failed duplicate
+    /// does not hide the accepted copy.
+    pub(crate) fn record_receipt(&mut self, from: &str, to: &str, receipt: &TxReceipt) {
+        for address in [from, to] {
+            let Some(entries) = self.address_txs.get_mut(address) else {
+                continue;
+            };
+            for entry in entries.iter_mut().filter(|e| e.tx_id == receipt.tx_id) {
+                if receipt.is_accepted() {
+                    entry.block_hash = receipt.block_hash.clone();
+                    entry.accepting_block = Some(receipt.accepting_block.clone());
+                } else if entry.block_hash == receipt.block_hash {
+                    entry.accepting_block = None;
+                }
+            }
+        }
+    }
+
+    /// Get the blocks containing a transaction
+    pub fn blocks_containing(&self, tx_id: &str) -> &[String] {
+        self.tx_blocks
+            .get(tx_id)
+            .map(|b| b.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Get the ids of the transactions in a block
+    pub fn block_transactions(&self, block_hash: &str) -> &[String] {
+        self.block_txs
+            .get(block_hash)
+            .map(|t| t.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Get a page of an address' transaction history, oldest first
+    pub fn address_history(
+        &self,
+        address: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Vec<&AddressTxEntry> {
+        self.address_txs
+            .get(address)
+            .map(|entries| entries.iter().skip(offset).take(limit).collect())
+            .unwrap_or_default()
+    }
+
+    /// Get the number of history entries of an address
+    pub fn address_tx_count(&self, address: &str) -> usize {
+        self.address_txs.get(address).map_or(0, |e| e.len())
+    }
+}
@@ -0,0 +1,196 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use super::{Block, BlockDAG, ConsensusParams, Transaction};
+
+/// Version of the JSON fixture schema
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+/// JSON fixture of a BlockDAG scenario, schema documented on `BlockDAG::import_json`
+#[derive(Debug, Serialize, Deserialize)]
+struct DagFixture {
+    version: u32,
+    #[serde(default)]
+    params: ConsensusParams,
+    #[serde(default)]
+    accounts: Vec<AccountFixture>,
+    #[serde(default)]
+    blocks: Vec<BlockFixture>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct AccountFixture {
+    address: String,
+    balance: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BlockFixture {
+    hash: String,
+    parents: Vec<String>,
+    timestamp: u64,
+    #[serde(default)]
+    transactions: Vec<TxFixture>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TxFixture {
+    id: String,
+    from: String,
+    to: String,
+    amount: u64,
+    #[serde(default)]
+    fee: u64,
+    nonce: u64,
+}
+
+impl BlockDAG {
+    /// Export the DAG as a JSON fixture (see `import_json` for the schema)
+    ///
+    /// Blocks are written in topological order, ties broken by (timestamp, hash).
+    /// Execution results are not exported. Pruned DAGs cannot be exported.
+    pub fn export_json(&self) -> Result<String, String> {
+        if !self.pruned_headers.is_empty() {
+            return Err("Cannot export a pruned DAG".to_string());
+        }
+
+        let mut params = self.params.clone();
+        let accounts = std::mem::take(&mut params.genesis.allocations)
+            .into_iter()
+            .map(|(address, balance)| AccountFixture { address, balance })
+            .collect();
+
+        let blocks = self
+            .blocks_in_topological_order()
+            .into_iter()
+            .filter(|block| block.hash != self.params.genesis.hash)
+            .map(|block| BlockFixture {
+                hash: block.hash.clone(),
+                parents: block.parent_hashes.clone(),
+                timestamp: block.timestamp,
+                transactions: block
+                    .transactions
+                    .iter()
+                    .map(|tx| TxFixture {
+                        id: tx.id.clone(),
+                        from: tx.from.clone(),
+                        to: tx.to.clone(),
+                        amount: tx.amount,
+                        fee: tx.fee,
+                        nonce: tx.nonce,
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        let fixture = DagFixture {
+            version: JSON_SCHEMA_VERSION,
+            params,
+            accounts,
+            blocks,
+        };
+        serde_json::to_string_pretty(&fixture).map_err(|e| e.to_string())
+    }
+
+    /// Build a DAG from a JSON fixture
+    ///
+    /// Schema (version 1):
+    ///
+    /// ```json
+    /// {
+    ///   "version": 1,
+    ///   "params": {
+    ///     "k": 3,
+    ///     "network_delay_ms": 0,
+    ///     "genesis": { "hash": "genesis", "timestamp": 0 }
+    ///   },
+    ///   "accounts": [{ "address": "alice", "balance": 1000 }],
+    ///   "blocks": [{
+    ///     "hash": "b1",
+    ///     "parents": ["genesis"],
+    ///     "timestamp": 1,
+    ///     "transactions": [{ "id": "tx1", "from": "alice", "to": "bob", "amount": 100, "nonce": 0 }]
+    ///   }]
+    /// }
+    /// ```
+    ///
+    /// `params` accepts any `ConsensusParams` field, missing ones default to `mainnet_like`;
+    /// the genesis is resealed for them and they must pass `ConsensusParams::validate`,
+    /// so a k below the mainnet recommendation needs a matching `network_delay_ms`.
+    /// `accounts` are the genesis allocations. `blocks` exclude genesis, must be listed
+    /// parents first and are added with `add_block`, so every consensus rule applies.
+    /// A transaction `fee` defaults to 0.
+    pub fn import_json(json: &str) -> Result<Self, String> {
+        let fixture: DagFixture =
+            serde_json::from_str(json).map_err(|e| format!("Invalid DAG fixture: {}", e))?;
+        if fixture.version != JSON_SCHEMA_VERSION {
+            return Err(format!(
+                "Unsupported DAG fixture version {}",
+                fixture.version
+            ));
+        }
+
+        let mut params = fixture.params;
+        let allocations: BTreeMap<String, u64> = fixture
+            .accounts
+            .into_iter()
+            .map(|account| (account.address, account.balance))
+            .collect();
+        params.genesis.allocations.extend(allocations);
+        let mut dag = Self::with_params(params.seal_genesis())
+            .map_err(|e| format!("Invalid DAG fixture params: {}", e))?;
+
+        for block in fixture.blocks {
+            let transactions = block
+                .transactions
+                .into_iter()
+                .map(|tx| {
+                    Transaction::new(tx.id, tx.from, tx.to, tx.amount, tx.nonce).with_fee(tx.fee)
+                })
+                .collect();
+            let hash = block.hash.clone();
+            dag.add_block(Block::new(
+                block.hash,
+                block.parents,
+                transactions,
+                block.timestamp,
+            ))
+            .map_err(|e| format!("Block '{}' rejected: {}", hash, e))?;
+        }
+        Ok(dag)
+    }
+
+    /// Blocks sorted topologically, ties broken by (timestamp, hash)
+    fn blocks_in_topological_order(&self) -> Vec<&Block> {
+        let mut in_degree: HashMap<&str, usize> = self
+            .blocks
+            .values()
+            .map(|block| (block.hash.as_str(), block.parent_hashes.len()))
+            .collect();
+        let mut ready: Vec<&Block> = self
+            .blocks
+            .values()
+            .filter(|block| block.parent_hashes.is_empty())
+            .collect();
+        let mut ordered = Vec::with_capacity(self.blocks.len());
+
+        while !ready.is_empty() {
+            ready.sort_by(|a, b| {
+                b.timestamp
+                    .cmp(&a.timestamp)
+                    .then_with(|| b.hash.cmp(&a.hash))
+            });
+            let block = ready.pop().unwrap();
+            ordered.push(block);
+            for child in &self.children_mapping[&block.hash] {
+                let degree = in_degree.get_mut(child.as_str()).unwrap();
+                *degree -= 1;
+                if *degree == 0 {
+                    ready.push(&self.blocks[child]);
+                }
+            }
+        }
+        ordered
+    }
+}
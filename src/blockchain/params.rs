@@ -0,0 +1,379 @@
+use super::codec::MAX_ENCODED_PARENTS;
+use super::genesis::GenesisConfig;
+use super::hashing::Fnv1a;
+
+/// Default GHOSTDAG k parameter, recommended for one block per second and 5s network delay
+pub const DEFAULT_K: usize = 18;
+
+/// Default GHOSTDAG security parameter: tolerated probability of an honest block turning red
+pub const DEFAULT_SECURITY_DELTA: f64 = 0.01;
+
+/// Default finality depth, in blue score
+pub const DEFAULT_FINALITY_DEPTH: u64 = 86_400;
+
+/// Default merge depth bound, in blue score
+pub const DEFAULT_MERGE_DEPTH: u64 = 3_600;
+
+/// Default maximum number of parents a block may reference
+pub const DEFAULT_MAX_BLOCK_PARENTS: usize = 10;
+
+/// Default maximum mergeset size of a block
+pub const DEFAULT_MERGESET_SIZE_LIMIT: usize = 180;
+
+/// Largest k `recommended_k` searches before giving up
+const MAX_RECOMMENDED_K: usize = 100_000;
+
+/// Recommended GHOSTDAG k for a block rate and network propagation delay (PHANTOM paper)
+///
+/// Honest blocks created within `2 * delay` of each other are concurrent. Their number
+/// follows a Poisson distribution with mean `x = 2 * delay * rate`, and k is the smallest
+/// value such that more than k concurrent blocks occur with probability below `delta`.
+/// Fails for negative or non-finite inputs, a `delta` outside (0, 1), or if k would
+/// exceed 100 000.
+pub fn recommended_k(
+    blocks_per_second: f64,
+    network_delay_secs: f64,
+    delta: f64,
+) -> Result<usize, String> {
+    let x = 2.0 * network_delay_secs * blocks_per_second;
+    if !(x.is_finite() && x >= 0.0) {
+        return Err(format!(
+            "Cannot derive k for {} blocks per second and a {}s network delay",
+            blocks_per_second, network_delay_secs
+        ));
+    }
+    if !(delta > 0.0 && delta < 1.0) {
+        return Err("security_delta must be between 0 and 1".to_string());
+    }
+
+    // Summed in log space: e^-x underflows and x^k / k! overflows for large x
+    let ln_x = x.ln();
+    let mut ln_p = -x; // ln P(Poisson(x) = k)
+    let mut ln_cdf = ln_p; // ln P(Poisson(x) <= k)
+    for k in 0..=MAX_RECOMMENDED_K {
+        if -ln_cdf.exp_m1() < delta {
+            return Ok(k);
+        }
+        ln_p += ln_x - ((k + 1) as f64).ln();
+        let (high, low) = (ln_cdf.max(ln_p), ln_cdf.min(ln_p));
+        ln_cdf = high + (low - high).exp().ln_1p();
+    }
+    Err(format!(
+        "Recommended k exceeds {} for {} blocks per second and a {}s network delay",
+        MAX_RECOMMENDED_K, blocks_per_second, network_delay_secs
+    ))
+}
+
+/// Consensus parameters of a BlockDAG network
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct ConsensusParams {
+    pub k: usize,                   // GHOSTDAG parameter, controls anticone size
+    pub max_block_parents: usize,   // Maximum number of parents per block
+    pub mergeset_size_limit: usize, // Maximum mergeset size per block
+    pub target_block_time_ms: u64,  // Expected interval between blocks
+    pub network_delay_ms: u64,      // Expected block propagation delay
+    pub security_delta: f64,        // Tolerated probability of an honest block turning red
+    pub daa_window_size: u64,       // Number of blocks the difficulty adjustment looks at
+    pub finality_depth: u64,        // Blue score depth beyond which reorgs are rejected
+    pub merge_depth: u64,           // Blue score depth beyond which red blocks may not be merged
+    pub coinbase_maturity: u64,     // Blue score depth before block rewards can be spent
+    pub genesis: GenesisConfig,
+}
+
+impl ConsensusParams {
+    /// Parameters modeled after a production network: one block per second
+    pub fn mainnet_like() -> Self {
+        Self {
+            k: DEFAULT_K,
+            max_block_parents: DEFAULT_MAX_BLOCK_PARENTS,
+            mergeset_size_limit: DEFAULT_MERGESET_SIZE_LIMIT,
+            target_block_time_ms: 1_000,
+            network_delay_ms: 5_000,
+            security_delta: DEFAULT_SECURITY_DELTA,
+            daa_window_size: 2_641,
+            finality_depth: DEFAULT_FINALITY_DEPTH,
+            merge_depth: DEFAULT_MERGE_DEPTH,
+            coinbase_maturity: 100,
+            genesis: GenesisConfig::default(),
+        }
+        .seal_genesis()
+    }
+
+    /// Public test network: mainnet rules with a shorter finality window
+    pub fn testnet() -> Self {
+        Self {
+            finality_depth: 43_200,
+            merge_depth: 1_800,
+            ..Self::mainnet_like()
+        }
+        .seal_genesis()
+    }
+
+    /// Developer network: short finality and maturity for quick iteration
+    pub fn devnet() -> Self {
+        Self {
+            daa_window_size: 263,
+            finality_depth: 1_000,
+            merge_depth: 100,
+            coinbase_maturity: 10,
+            ..Self::mainnet_like()
+        }
+        .seal_genesis()
+    }
+
+    /// Simulation network: fast blocks and tiny windows for local simulations
+    pub fn simnet() -> Self {
+        Self {
+            max_block_parents: 5,
+            daa_window_size: 100,
+            finality_depth: 100,
+            merge_depth: 20,
+            coinbase_maturity: 1,
+            ..Self::mainnet_like().with_timing(100, 100)
+        }
+        .seal_genesis()
+    }
+
+    /// Use the given genesis, sealed for these parameters
+    pub fn with_genesis(mut self, genesis: GenesisConfig) -> Self {
+        self.genesis = genesis;
+        self.seal_genesis()
+    }
+
+    /// Bind the genesis to the current parameters by recording their hash
+    ///
+    /// Must be called again after changing any parameter, or validation fails.
+    pub fn seal_genesis(mut self) -> Self {
+        self.genesis.params_hash = self.hash();
+        self
+    }
+
+    /// Deterministic hash of the consensus rules (the genesis definition excluded)
+    pub fn hash(&self) -> u64 {
+        let mut hasher = Fnv1a::new();
+        for value in [
+            self.k as u64,
+            self.max_block_parents as u64,
+            self.mergeset_size_limit as u64,
+            self.target_block_time_ms,
+            self.network_delay_ms,
+            self.security_delta.to_bits(),
+            self.daa_window_size,
+            self.finality_depth,
+            self.merge_depth,
+            self.coinbase_maturity,
+        ] {
+            hasher.write_u64(value);
+        }
+        hasher.finish()
+    }
+
+    /// Set the block time and network delay, deriving k and the mergeset size limit
+    ///
+    /// If no k can be derived for them, k is left unchanged and `validate` reports it.
+    pub fn with_timing(mut self, target_block_time_ms: u64, network_delay_ms: u64) -> Self {
+        self.target_block_time_ms = target_block_time_ms;
+        self.network_delay_ms = network_delay_ms;
+        if let Ok(k) = self.recommended_k() {
+            self.k = k;
+            self.mergeset_size_limit = k.max(1) * 10;
+        }
+        self
+    }
+
+    /// Recommended k for the configured block time, network delay and security parameter
+    pub fn recommended_k(&self) -> Result<usize, String> {
+        recommended_k(
+            1_000.0 / self.target_block_time_ms.max(1) as f64,
+            self.network_delay_ms as f64 / 1_000.0,
+            self.security_delta,
+        )
+    }
+
+    /// Check the parameters are consistent
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_block_parents == 0 {
+            return Err("max_block_parents must be at least 1".to_string());
+        }
+        if self.max_block_parents > MAX_ENCODED_PARENTS {
+            return Err(format!(
+                "max_block_parents ({}) exceeds the {} parents a header can encode",
+                self.max_block_parents, MAX_ENCODED_PARENTS
+            ));
+        }
+        if self.mergeset_size_limit < self.k + 1 {
+            return Err(format!(
+                "mergeset_size_limit ({}) must be larger than k ({})",
+                self.mergeset_size_limit, self.k
+            ));
+        }
+        if self.target_block_time_ms == 0 {
+            return Err("target_block_time_ms must be positive".to_string());
+        }
+        if !(self.security_delta > 0.0 && self.security_delta < 1.0) {
+            return Err("security_delta must be between 0 and 1".to_string());
+        }
+        let recommended = self.recommended_k()?;
+        if self.k < recommended {
+            return Err(format!(
+                "k ({}) is below the recommended k ({}) for the block time and network delay",
+                self.k, recommended
+            ));
+        }
+        if self.daa_window_size == 0 {
+            return Err("daa_window_size must be positive".to_string());
+        }
+        if self.merge_depth > self.finality_depth {
+            return Err(format!(
+                "merge_depth ({}) must not exceed finality_depth ({})",
+                self.merge_depth, self.finality_depth
+            ));
+        }
+        if self.genesis.hash.is_empty() {
+            return Err("genesis hash must not be empty".to_string());
+        }
+        if self.genesis.params_hash != self.hash() {
+            return Err(
+                "genesis was sealed for different consensus parameters, call seal_genesis"
+                    .to_string(),
+            );
+        }
+        Ok(())
+    }
+}
+
+impl Default for ConsensusParams {
+    fn default() -> Self {
+        Self::mainnet_like()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets_are_valid() {
+        for params in [
+            ConsensusParams::mainnet_like(),
+            ConsensusParams::testnet(),
+            ConsensusParams::devnet(),
+            ConsensusParams::simnet(),
+        ] {
+            assert!(params.validate().is_ok(), "{:?}", params);
+        }
+    }
+
+    #[test]
+    fn test_genesis_sealed_for_params() {
+        let params = ConsensusParams::devnet();
+        assert_eq!(params.genesis.params_hash, params.hash());
+        assert_ne!(params.hash(), ConsensusParams::mainnet_like().hash());
+
+        // Changing a rule without resealing is caught
+        let params = ConsensusParams {
+            coinbase_maturity: 5,
+            ..ConsensusParams::devnet()
+        };
+        assert!(params.validate().unwrap_err().contains("seal_genesis"));
+        assert!(params.seal_genesis().validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_rejects_inconsistent_params() {
+        let params = ConsensusParams {
+            merge_depth: 200,
+            finality_depth: 100,
+            ..ConsensusParams::simnet()
+        };
+        assert!(params.validate().unwrap_err().contains("merge_depth"));
+
+        let params = ConsensusParams {
+            max_block_parents: 0,
+            ..ConsensusParams::simnet()
+        };
+        assert!(params.validate().is_err());
+
+        let params = ConsensusParams {
+            daa_window_size: 0,
+            ..ConsensusParams::simnet()
+        }
+        .seal_genesis();
+        assert!(params.validate().unwrap_err().contains("daa_window_size"));
+
+        let params = ConsensusParams {
+            max_block_parents: MAX_ENCODED_PARENTS + 1,
+            ..ConsensusParams::simnet()
+        };
+        assert!(params.validate().unwrap_err().contains("max_block_parents"));
+
+        let params = ConsensusParams {
+            k: 400,
+            ..ConsensusParams::simnet()
+        };
+        assert!(
+            params
+                .validate()
+                .unwrap_err()
+                .contains("mergeset_size_limit")
+        );
+
+        let params = ConsensusParams {
+            k: 2,
+            ..ConsensusParams::simnet()
+        };
+        assert!(params.validate().unwrap_err().contains("recommended k"));
+    }
+
+    #[test]
+    fn test_recommended_k() {
+        // One block per second with 5s delay matches the default k
+        assert_eq!(
+            recommended_k(1.0, 5.0, DEFAULT_SECURITY_DELTA),
+            Ok(DEFAULT_K)
+        );
+        assert_eq!(
+            ConsensusParams::mainnet_like().recommended_k(),
+            Ok(DEFAULT_K)
+        );
+
+        // No delay means no honest concurrency
+        assert_eq!(recommended_k(1.0, 0.0, DEFAULT_SECURITY_DELTA), Ok(0));
+
+        // Faster blocks or a stricter security parameter require a larger k
+        assert!(recommended_k(10.0, 5.0, DEFAULT_SECURITY_DELTA).unwrap() > DEFAULT_K);
+        assert!(recommended_k(1.0, 5.0, 0.001).unwrap() > DEFAULT_K);
+
+        let simnet = ConsensusParams::simnet();
+        assert_eq!(simnet.recommended_k(), Ok(simnet.k));
+        assert_eq!(simnet.mergeset_size_limit, simnet.k * 10);
+    }
+
+    #[test]
+    fn test_recommended_k_for_high_concurrency() {
+        // 1ms blocks with a 400ms delay: x = 800, where e^-x underflows
+        let k = recommended_k(1_000.0, 0.4, DEFAULT_SECURITY_DELTA).unwrap();
+        assert!(k > 800 && k < 900, "{}", k);
+        // x = 720, where x^k / k! overflows
+        let k = recommended_k(1_000.0, 0.36, DEFAULT_SECURITY_DELTA).unwrap();
+        assert!(k > 720 && k < 800, "{}", k);
+
+        let params = ConsensusParams::mainnet_like()
+            .with_timing(1, 400)
+            .seal_genesis();
+        assert!(params.validate().is_ok());
+
+        // Unusable inputs are errors instead of hanging or returning a bogus k
+        assert!(recommended_k(1e9, 1e9, DEFAULT_SECURITY_DELTA).is_err());
+        assert!(recommended_k(1.0, f64::INFINITY, DEFAULT_SECURITY_DELTA).is_err());
+        assert!(recommended_k(1.0, 5.0, 0.0).is_err());
+        let params = ConsensusParams {
+            network_delay_ms: u64::MAX,
+            ..ConsensusParams::mainnet_like()
+        }
+        .seal_genesis();
+        assert!(params.validate().unwrap_err().contains("exceeds"));
+    }
+}
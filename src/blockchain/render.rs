@@ -0,0 +1,188 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+use super::{Block, BlockColor, BlockDAG};
+use crate::render::{dot_quote, mermaid_quote};
+
+const BLUE_FILL: &str = "#4a90d9";
+const RED_FILL: &str = "#d9534f";
+const SELECTED_PARENT_EDGE: &str = "#1b4f8a";
+
+/// Options for `BlockDAG::to_dot_with` and `BlockDAG::to_mermaid_with`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RenderOptions {
+    pub cluster_by_timestamp: bool, // Group blocks with the same timestamp
+}
+
+impl RenderOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Group blocks with the same timestamp
+    pub fn with_timestamp_clusters(mut self, cluster_by_timestamp: bool) -> Self {
+        self.cluster_by_timestamp = cluster_by_timestamp;
+        self
+    }
+}
+
+impl BlockDAG {
+    /// Render the DAG in Graphviz DOT format
+    ///
+    /// Blocks are colored by `BlockColor` and labeled with their position in the blue
+    /// order, and selected-parent edges are drawn bold. Edges point from parent to child.
+    pub fn to_dot(&self) -> String {
+        self.to_dot_with(&RenderOptions::default())
+    }
+
+    /// Render the DAG in Graphviz DOT format with the given options
+    pub fn to_dot_with(&self, options: &RenderOptions) -> String {
+        let mut out = String::from(
+            "digraph BlockDAG {\n    rankdir=LR;\n    node [shape=box, style=\"rounded,filled\", fontcolor=white];\n",
+        );
+
+        let node = |block: &Block| {
+            let fill = match block.color {
+                BlockColor::Blue => BLUE_FILL,
+                BlockColor::Red => RED_FILL,
+            };
+            format!(
+                "{} [label={}, fillcolor=\"{}\"];",
+                dot_quote(&block.hash),
+                dot_quote(&block_label(block)),
+                fill
+            )
+        };
+        if options.cluster_by_timestamp {
+            for (timestamp, blocks) in self.blocks_by_timestamp() {
+                writeln!(out, "    subgraph \"cluster_t{}\" {{", timestamp).unwrap();
+                writeln!(out, "        label=\"t={}\";", timestamp).unwrap();
+                for block in blocks {
+                    writeln!(out, "        {}", node(block)).unwrap();
+                }
+                out.push_str("    }\n");
+            }
+        } else {
+            for block in self.sorted_blocks() {
+                writeln!(out, "    {}", node(block)).unwrap();
+            }
+        }
+
+        for (parent, child, selected) in self.render_edges() {
+            let style = if selected {
+                format!(" [color=\"{}\", penwidth=3]", SELECTED_PARENT_EDGE)
+            } else {
+                " [color=gray]".to_string()
+            };
+            writeln!(
+                out,
+                "    {} -> {}{};",
+                dot_quote(parent),
+                dot_quote(child),
+                style
+            )
+            .unwrap();
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Render the DAG as a Mermaid flowchart
+    ///
+    /// Same conventions as `to_dot`; selected-parent edges use thick arrows (`==>`).
+    pub fn to_mermaid(&self) -> String {
+        self.to_mermaid_with(&RenderOptions::default())
+    }
+
+    /// Render the DAG as a Mermaid flowchart with the given options
+    pub fn to_mermaid_with(&self, options: &RenderOptions) -> String {
+        let blocks = self.sorted_blocks();
+        let ids: HashMap<&str, usize> = blocks
+            .iter()
+            .enumerate()
+            .map(|(i, b)| (b.hash.as_str(), i))
+            .collect();
+        let id = |hash: &str| ids[hash];
+        let node = |block: &Block| {
+            let class = match block.color {
+                BlockColor::Blue => "blue",
+                BlockColor::Red => "red",
+            };
+            format!(
+                "b{}[{}]:::{}",
+                id(&block.hash),
+                mermaid_quote(&block_label(block)),
+                class
+            )
+        };
+
+        let mut out = String::from("graph LR\n");
+        if options.cluster_by_timestamp {
+            for (timestamp, cluster) in self.blocks_by_timestamp() {
+                writeln!(out, "    subgraph t{}[\"t={}\"]", timestamp, timestamp).unwrap();
+                for block in cluster {
+                    writeln!(out, "        {}", node(block)).unwrap();
+                }
+                out.push_str("    end\n");
+            }
+        } else {
+            for block in &blocks {
+                writeln!(out, "    {}", node(block)).unwrap();
+            }
+        }
+
+        for (parent, child, selected) in self.render_edges() {
+            let arrow = if selected { "==>" } else { "-->" };
+            writeln!(out, "    b{} {} b{}", id(parent), arrow, id(child)).unwrap();
+        }
+        writeln!(out, "    classDef blue fill:{},color:#fff", BLUE_FILL).unwrap();
+        writeln!(out, "    classDef red fill:{},color:#fff", RED_FILL).unwrap();
+        out
+    }
+
+    /// Blocks sorted by (timestamp, hash)
+    fn sorted_blocks(&self) -> Vec<&Block> {
+        let mut blocks: Vec<&Block> = self.blocks.values().collect();
+        blocks.sort_by(|a, b| {
+            a.timestamp
+                .cmp(&b.timestamp)
+                .then_with(|| a.hash.cmp(&b.hash))
+        });
+        blocks
+    }
+
+    fn blocks_by_timestamp(&self) -> BTreeMap<u64, Vec<&Block>> {
+        let mut clusters: BTreeMap<u64, Vec<&Block>> = BTreeMap::new();
+        for block in self.sorted_blocks() {
+            clusters.entry(block.timestamp).or_default().push(block);
+        }
+        clusters
+    }
+
+    /// Parent-to-child edges between present blocks, flagged when the parent is the
+    /// child's selected parent
+    fn render_edges(&self) -> Vec<(&str, &str, bool)> {
+        let mut edges = Vec::new();
+        for block in self.sorted_blocks() {
+            let selected_parent = self.selected_parent(&block.hash);
+            let mut parents: Vec<&String> = block
+                .parent_hashes
+                .iter()
+                .filter(|p| self.blocks.contains_key(*p))
+                .collect();
+            parents.sort();
+            for parent in parents {
+                let selected = selected_parent == Some(parent.as_str());
+                edges.push((parent.as_str(), block.hash.as_str(), selected));
+            }
+        }
+        edges
+    }
+}
+
+fn block_label(block: &Block) -> String {
+    match block.color {
+        BlockColor::Blue => format!("{}\nblue #{}", block.hash, block.weight),
+        BlockColor::Red => format!("{}\nred", block.hash),
+    }
+}
@@ -0,0 +1,640 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use super::codec::{CodecError, Decode, Encode, MAX_ENCODED_TRANSACTIONS, Reader, Writer};
+use super::hashing::Fnv1a;
+use super::{Account, BlockColor, BlockHeader, Transaction, TxStatus};
+
+/// Magic bytes at the start of a `FileStore` log
+const LOG_MAGIC: &[u8; 8] = b"DAGLOG01";
+
+/// Maximum length of a store key, in bytes
+const MAX_KEY_LEN: usize = 4096;
+
+/// A single write of a batch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BatchOp {
+    Put(String, Vec<u8>),
+    Delete(String),
+}
+
+/// Writes applied atomically: after a crash either all of them are visible or none
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WriteBatch {
+    ops: Vec<BatchOp>,
+}
+
+impl WriteBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn put(&mut self, key: impl Into<String>, value: Vec<u8>) {
+        self.ops.push(BatchOp::Put(key.into(), value));
+    }
+
+    pub fn delete(&mut self, key: impl Into<String>) {
+        self.ops.push(BatchOp::Delete(key.into()));
+    }
+
+    pub fn ops(&self) -> &[BatchOp] {
+        &self.ops
+    }
+
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+}
+
+/// Key-value storage backend for a BlockDAG
+pub trait Store {
+    /// Get the value stored under a key
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, String>;
+
+    /// Get all entries whose key starts with `prefix`, sorted by key
+    fn scan_prefix(&self, prefix: &str) -> Result<Vec<(String, Vec<u8>)>, String>;
+
+    /// Apply a batch atomically
+    fn write(&mut self, batch: WriteBatch) -> Result<(), String>;
+}
+
+/// Volatile in-memory store
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStore {
+    data: BTreeMap<String, Vec<u8>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+impl Store for MemoryStore {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, String> {
+        Ok(self.data.get(key).cloned())
+    }
+
+    fn scan_prefix(&self, prefix: &str) -> Result<Vec<(String, Vec<u8>)>, String> {
+        Ok(scan(&self.data, prefix))
+    }
+
+    fn write(&mut self, batch: WriteBatch) -> Result<(), String> {
+        apply(&mut self.data, batch.ops);
+        Ok(())
+    }
+}
+
+/// Embedded on-disk store: an append-only log replayed into memory on open
+///
+/// Each batch is appended as one record `[payload length: u32][checksum: u64][payload]`
+/// and synced before `write` returns. A failed write is truncated away, and when
+/// reopening, a torn or corrupt record ends the log and is truncated away as well, so an
+/// interrupted batch is never partially applied.
+#[derive(Debug)]
+pub struct FileStore {
+    path: PathBuf,
+    file: File,
+    data: BTreeMap<String, Vec<u8>>,
+    poisoned: bool, // A failed write could not be truncated away, writes are refused
+}
+
+impl FileStore {
+    /// Open the log at `path`, creating it if it does not exist
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(&path)
+            .map_err(|e| format!("Failed to open '{}': {}", path.display(), e))?;
+
+        let mut contents = Vec::new();
+        file.read_to_end(&mut contents)
+            .map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?;
+
+        let mut data = BTreeMap::new();
+        if contents.is_empty() {
+            file.write_all(LOG_MAGIC)
+                .and_then(|_| file.sync_data())
+                .map_err(|e| format!("Failed to initialize '{}': {}", path.display(), e))?;
+        } else {
+            if !contents.starts_with(LOG_MAGIC) {
+                return Err(format!("'{}' is not a BlockDAG store", path.display()));
+            }
+            let valid_len = replay(&contents, &mut data);
+            if valid_len < contents.len() {
+                file.set_len(valid_len as u64)
+                    .and_then(|_| file.sync_data())
+                    .map_err(|e| format!("Failed to truncate '{}': {}", path.display(), e))?;
+            }
+        }
+
+        Ok(Self {
+            path,
+            file,
+            data,
+            poisoned: false,
+        })
+    }
+
+    /// Path of the log file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Rewrite the log keeping only live entries
+    ///
+    /// The new log is written to a temporary file and renamed over the old one, and the
+    /// directory is synced so the rename survives a crash.
+    pub fn compact(&mut self) -> Result<(), String> {
+        let tmp_path = self.path.with_extension("compact");
+        let mut batch = WriteBatch::new();
+        for (key, value) in &self.data {
+            batch.put(key.clone(), value.clone());
+        }
+
+        let mut contents = LOG_MAGIC.to_vec();
+        if !batch.is_empty() {
+            contents.extend(encode_record(&batch)?);
+        }
+        fs::write(&tmp_path, &contents)
+            .and_then(|_| File::open(&tmp_path)?.sync_all())
+            .and_then(|_| fs::rename(&tmp_path, &self.path))
+            .and_then(|_| sync_parent_dir(&self.path))
+            .map_err(|e| format!("Failed to compact '{}': {}", self.path.display(), e))?;
+
+        self.file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| format!("Failed to reopen '{}': {}", self.path.display(), e))?;
+        Ok(())
+    }
+}
+
+impl Store for FileStore {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, String> {
+        Ok(self.data.get(key).cloned())
+    }
+
+    fn scan_prefix(&self, prefix: &str) -> Result<Vec<(String, Vec<u8>)>, String> {
+        Ok(scan(&self.data, prefix))
+    }
+
+    fn write(&mut self, batch: WriteBatch) -> Result<(), String> {
+        if self.poisoned {
+            return Err(format!(
+                "'{}' has a torn record from a failed write, reopen it",
+                self.path.display()
+            ));
+        }
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let record = encode_record(&batch)?;
+        let len = self
+            .file
+            .metadata()
+            .map_err(|e| format!("Failed to read '{}': {}", self.path.display(), e))?
+            .len();
+
+        if let Err(e) = self
+            .file
+            .write_all(&record)
+            .and_then(|_| self.file.sync_data())
+        {
+            // Drop whatever part of the record reached the file
+            if self
+                .file
+                .set_len(len)
+                .and_then(|_| self.file.sync_data())
+                .is_err()
+            {
+                self.poisoned = true;
+            }
+            return Err(format!("Failed to write '{}': {}", self.path.display(), e));
+        }
+        apply(&mut self.data, batch.ops);
+        Ok(())
+    }
+}
+
+fn scan(data: &BTreeMap<String, Vec<u8>>, prefix: &str) -> Vec<(String, Vec<u8>)> {
+    data.range(prefix.to_string()..)
+        .take_while(|(key, _)| key.starts_with(prefix))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect()
+}
+
+fn apply(data: &mut BTreeMap<String, Vec<u8>>, ops: Vec<BatchOp>) {
+    for op in ops {
+        match op {
+            BatchOp::Put(key, value) => {
+                data.insert(key, value);
+            }
+            BatchOp::Delete(key) => {
+                data.remove(&key);
+            }
+        }
+    }
+}
+
+/// Make a rename or file creation in the directory of `path` durable
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    File::open(dir)?.sync_all()
+}
+
+/// Directories cannot be opened for syncing outside Unix
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+fn checksum(payload: &[u8]) -> u64 {
+    let mut hasher = Fnv1a::new();
+    hasher.write(payload);
+    hasher.finish()
+}
+
+fn encode_record(batch: &WriteBatch) -> Result<Vec<u8>, String> {
+    let write = || {
+        let mut payload = Writer::new();
+        payload.len("batch", batch.len(), u32::MAX as usize)?;
+        for op in batch.ops() {
+            match op {
+                BatchOp::Put(key, value) => {
+                    payload.u8(0);
+                    payload.bytes("key", key.as_bytes(), MAX_KEY_LEN)?;
+                    payload.bytes("value", value, u32::MAX as usize)?;
+                }
+                BatchOp::Delete(key) => {
+                    payload.u8(1);
+                    payload.bytes("key", key.as_bytes(), MAX_KEY_LEN)?;
+                }
+            }
+        }
+        Ok(payload.finish())
+    };
+    let payload = write().map_err(|e: CodecError| format!("Cannot encode batch: {}", e))?;
+    let len = u32::try_from(payload.len()).map_err(|_| {
+        format!(
+            "Batch of {} bytes is too large for one record",
+            payload.len()
+        )
+    })?;
+
+    let mut record = Vec::with_capacity(payload.len() + 12);
+    record.extend_from_slice(&len.to_le_bytes());
+    record.extend_from_slice(&checksum(&payload).to_le_bytes());
+    record.extend_from_slice(&payload);
+    Ok(record)
+}
+
+fn decode_record(payload: &[u8]) -> Result<Vec<BatchOp>, CodecError> {
+    let mut reader = Reader::new(payload);
+    let count = reader.len("batch", payload.len())?;
+    let mut ops = Vec::with_capacity(count);
+    for _ in 0..count {
+        let op = match reader.u8()? {
+            0 => BatchOp::Put(read_key(&mut reader)?, read_value(&mut reader)?),
+            1 => BatchOp::Delete(read_key(&mut reader)?),
+            tag => {
+                return Err(CodecError::InvalidTag {
+                    what: "batch operation",
+                    tag,
+                });
+            }
+        };
+        ops.push(op);
+    }
+    reader.finish()?;
+    Ok(ops)
+}
+
+fn read_key(reader: &mut Reader<'_>) -> Result<String, CodecError> {
+    let bytes = reader.bytes("key", MAX_KEY_LEN)?;
+    String::from_utf8(bytes.to_vec()).map_err(|_| CodecError::InvalidUtf8)
+}
+
+fn read_value(reader: &mut Reader<'_>) -> Result<Vec<u8>, CodecError> {
+    Ok(reader.bytes("value", u32::MAX as usize)?.to_vec())
+}
+
+/// Apply every intact record of a log, returning the length of the valid prefix
+fn replay(contents: &[u8], data: &mut BTreeMap<String, Vec<u8>>) -> usize {
+    let mut pos = LOG_MAGIC.len();
+    while let Some(header) = contents.get(pos..pos + 12) {
+        let len = u32::from_le_bytes(header[..4].try_into().unwrap()) as usize;
+        let sum = u64::from_le_bytes(header[4..].try_into().unwrap());
+        let Some(payload) = contents.get(pos + 12..pos + 12 + len) else {
+            break;
+        };
+        if checksum(payload) != sum {
+            break;
+        }
+        let Ok(ops) = decode_record(payload) else {
+            break;
+        };
+        apply(data, ops);
+        pos += 12 + len;
+    }
+    pos
+}
+
+// Value encodings of the BlockDAG records, built on the wire codec
+
+fn corrupt(key: &str, error: CodecError) -> String {
+    format!("Corrupt record '{}': {}", key, error)
+}
+
+pub(crate) fn encode_u64(value: u64) -> Vec<u8> {
+    value.to_le_bytes().to_vec()
+}
+
+pub(crate) fn decode_u64(key: &str, bytes: &[u8]) -> Result<u64, String> {
+    let mut reader = Reader::new(bytes);
+    let value = reader.u64().map_err(|e| corrupt(key, e))?;
+    reader.finish().map_err(|e| corrupt(key, e))?;
+    Ok(value)
+}
+
+pub(crate) fn encode_pruning(
+    pruning_point: &str,
+    pruned_blue_count: u64,
+    commitment: u64,
+) -> Result<Vec<u8>, CodecError> {
+    let mut writer = Writer::new();
+    writer.str("block hash", pruning_point)?;
+    writer.u64(pruned_blue_count);
+    writer.u64(commitment);
+    Ok(writer.finish())
+}
+
+pub(crate) fn decode_pruning(key: &str, bytes: &[u8]) -> Result<(String, u64, u64), String> {
+    let read = || {
+        let mut reader = Reader::new(bytes);
+        let value = (reader.str("block hash")?, reader.u64()?, reader.u64()?);
+        reader.finish()?;
+        Ok(value)
+    };
+    read().map_err(|e| corrupt(key, e))
+}
+
+pub(crate) fn encode_header(header: &BlockHeader) -> Result<Vec<u8>, CodecError> {
+    header.encode()
+}
+
+pub(crate) fn decode_header(key: &str, bytes: &[u8]) -> Result<BlockHeader, String> {
+    BlockHeader::decode(bytes).map_err(|e| corrupt(key, e))
+}
+
+/// Transactions in wire form, each followed by its local execution status
+pub(crate) fn encode_body(transactions: &[Transaction]) -> Result<Vec<u8>, CodecError> {
+    let mut writer = Writer::new();
+    writer.len(
+        "transaction list",
+        transactions.len(),
+        MAX_ENCODED_TRANSACTIONS,
+    )?;
+    for tx in transactions {
+        tx.encode_to(&mut writer)?;
+        match &tx.status {
+            TxStatus::Pending => writer.u8(0),
+            TxStatus::Executed => writer.u8(1),
+            TxStatus::Failed(reason) => {
+                writer.u8(2);
+                writer.str("failure reason", reason)?;
+            }
+            TxStatus::Reverted => writer.u8(3),
+        }
+    }
+    Ok(writer.finish())
+}
+
+pub(crate) fn decode_body(key: &str, bytes: &[u8]) -> Result<Vec<Transaction>, String> {
+    let read = || {
+        let mut reader = Reader::new(bytes);
+        let count = reader.len("transaction list", MAX_ENCODED_TRANSACTIONS)?;
+        let mut transactions = Vec::with_capacity(count.min(reader.remaining()));
+        for _ in 0..count {
+            let mut tx = Transaction::decode_from(&mut reader)?;
+            tx.status = match reader.u8()? {
+                0 => TxStatus::Pending,
+                1 => TxStatus::Executed,
+                2 => TxStatus::Failed(reader.str("failure reason")?),
+                3 => TxStatus::Reverted,
+                tag => {
+                    return Err(CodecError::InvalidTag {
+                        what: "transaction status",
+                        tag,
+                    });
+                }
+            };
+            transactions.push(tx);
+        }
+        reader.finish()?;
+        Ok(transactions)
+    };
+    read().map_err(|e| corrupt(key, e))
+}
+
+pub(crate) fn encode_ghostdag(color: &BlockColor, weight: u64) -> Vec<u8> {
+    let mut writer = Writer::new();
+    writer.u8(match color {
+        BlockColor::Blue => 0,
+        BlockColor::Red => 1,
+    });
+    writer.u64(weight);
+    writer.finish()
+}
+
+pub(crate) fn decode_ghostdag(key: &str, bytes: &[u8]) -> Result<(BlockColor, u64), String> {
+    let read = || {
+        let mut reader = Reader::new(bytes);
+        let color = match reader.u8()? {
+            0 => BlockColor::Blue,
+            1 => BlockColor::Red,
+            tag => {
+                return Err(CodecError::InvalidTag {
+                    what: "block color",
+                    tag,
+                });
+            }
+        };
+        let weight = reader.u64()?;
+        reader.finish()?;
+        Ok((color, weight))
+    };
+    read().map_err(|e| corrupt(key, e))
+}
+
+pub(crate) fn encode_account(account: &Account) -> Vec<u8> {
+    let mut writer = Writer::new();
+    writer.u64(account.balance);
+    writer.u64(account.nonce);
+    writer.finish()
+}
+
+pub(crate) fn decode_account(key: &str, address: &str, bytes: &[u8]) -> Result<Account, String> {
+    let read = || {
+        let mut reader = Reader::new(bytes);
+        let mut account = Account::new(address.to_string(), reader.u64()?);
+        account.nonce = reader.u64()?;
+        reader.finish()?;
+        Ok(account)
+    };
+    read().map_err(|e| corrupt(key, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "dag-demo-storage-{}-{}.log",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_memory_store_batch() {
+        let mut store = MemoryStore::new();
+        let mut batch = WriteBatch::new();
+        batch.put("a/1", vec![1]);
+        batch.put("a/2", vec![2]);
+        batch.put("b/1", vec![3]);
+        store.write(batch).unwrap();
+
+        let mut batch = WriteBatch::new();
+        batch.delete("a/1");
+        store.write(batch).unwrap();
+
+        assert_eq!(store.get("a/1").unwrap(), None);
+        assert_eq!(store.get("b/1").unwrap(), Some(vec![3]));
+        assert_eq!(
+            store.scan_prefix("a/").unwrap(),
+            vec![("a/2".to_string(), vec![2])]
+        );
+    }
+
+    #[test]
+    fn test_file_store_reopen_and_compact() {
+        let path = temp_path("reopen");
+        {
+            let mut store = FileStore::open(&path).unwrap();
+            let mut batch = WriteBatch::new();
+            batch.put("x", vec![1, 2, 3]);
+            batch.put("y", vec![4]);
+            store.write(batch).unwrap();
+
+            let mut batch = WriteBatch::new();
+            batch.put("x", vec![5]);
+            batch.delete("y");
+            store.write(batch).unwrap();
+        }
+
+        let mut store = FileStore::open(&path).unwrap();
+        assert_eq!(store.get("x").unwrap(), Some(vec![5]));
+        assert_eq!(store.get("y").unwrap(), None);
+
+        let before = fs::metadata(&path).unwrap().len();
+        store.compact().unwrap();
+        assert!(fs::metadata(&path).unwrap().len() < before);
+
+        let store = FileStore::open(&path).unwrap();
+        assert_eq!(
+            store.scan_prefix("").unwrap(),
+            vec![("x".to_string(), vec![5])]
+        );
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_file_store_discards_torn_batch() {
+        let path = temp_path("torn");
+        {
+            let mut store = FileStore::open(&path).unwrap();
+            let mut batch = WriteBatch::new();
+            batch.put("kept", vec![1]);
+            store.write(batch).unwrap();
+        }
+        let intact_len = fs::metadata(&path).unwrap().len();
+
+        // Simulate a crash in the middle of appending a second batch
+        let mut batch = WriteBatch::new();
+        batch.put("lost/1", vec![2]);
+        batch.put("lost/2", vec![3]);
+        let record = encode_record(&batch).unwrap();
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&record[..record.len() - 3]).unwrap();
+        drop(file);
+
+        let mut store = FileStore::open(&path).unwrap();
+        assert_eq!(store.get("kept").unwrap(), Some(vec![1]));
+        assert!(store.scan_prefix("lost/").unwrap().is_empty());
+        assert_eq!(fs::metadata(&path).unwrap().len(), intact_len);
+
+        // The log stays usable after recovery
+        let mut batch = WriteBatch::new();
+        batch.put("after", vec![4]);
+        store.write(batch).unwrap();
+        let store = FileStore::open(&path).unwrap();
+        assert_eq!(store.get("after").unwrap(), Some(vec![4]));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_file_store_refuses_writes_after_failed_write() {
+        let path = temp_path("failed");
+        let mut store = FileStore::open(&path).unwrap();
+        let mut batch = WriteBatch::new();
+        batch.put("kept", vec![1]);
+        store.write(batch).unwrap();
+
+        // A read-only handle makes both the append and its rollback fail
+        store.file = File::open(&path).unwrap();
+        let mut batch = WriteBatch::new();
+        batch.put("lost", vec![2]);
+        assert!(store.write(batch.clone()).is_err());
+        assert!(store.write(batch).unwrap_err().contains("reopen"));
+        assert_eq!(store.get("lost").unwrap(), None);
+
+        let store = FileStore::open(&path).unwrap();
+        assert_eq!(store.get("kept").unwrap(), Some(vec![1]));
+        assert_eq!(store.get("lost").unwrap(), None);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_file_store_rejects_foreign_file() {
+        let path = temp_path("foreign");
+        fs::write(&path, b"not a log").unwrap();
+        assert!(FileStore::open(&path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
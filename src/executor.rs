@@ -0,0 +1,295 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Display;
+use std::hash::Hash;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, mpsc};
+use std::thread;
+
+use crate::{Dag, DagNode};
+
+/// Result of one node run by an `Executor`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TaskOutcome<T> {
+    Completed(T),
+    Failed(String),
+    Skipped,   // A parent failed or was skipped
+    Cancelled, // Not started because the run was cancelled
+}
+
+/// Shared flag to stop an `Executor` run; clones refer to the same flag
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stop starting new tasks; running ones are left to finish
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Runs a task per node on a pool of threads, each node once all its parents completed
+#[derive(Debug, Clone)]
+pub struct Executor {
+    max_concurrency: usize, // Worker threads, at least 1
+    cancel: CancelToken,
+}
+
+impl Executor {
+    /// Executor with one worker per available CPU
+    pub fn new() -> Self {
+        let workers = thread::available_parallelism().map_or(1, |n| n.get());
+        Self {
+            max_concurrency: workers,
+            cancel: CancelToken::new(),
+        }
+    }
+
+    /// Run at most `max_concurrency` tasks at once
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(1);
+        self
+    }
+
+    /// Stop the run when `cancel` is cancelled
+    pub fn with_cancel_token(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Run `task` on every node and return each node's outcome
+    ///
+    /// Ready nodes are started in insertion order. When a task fails, or panics, its
+    /// descendants are skipped while independent nodes keep running.
+    pub fn run<K, V, E, T, F>(&self, dag: &Dag<K, V, E>, task: F) -> HashMap<K, TaskOutcome<T>>
+    where
+        K: Hash + Eq + Clone + Display + Sync,
+        V: Sync,
+        E: Sync,
+        T: Send,
+        F: Fn(&DagNode<K, V>) -> Result<T, String> + Sync,
+    {
+        let mut outcomes: HashMap<&K, TaskOutcome<T>> = HashMap::new();
+        let mut pending: HashMap<&K, usize> = dag
+            .parents
+            .iter()
+            .map(|(id, parents)| (id, parents.len()))
+            .collect();
+        let mut ready: BTreeMap<usize, &K> = pending
+            .iter()
+            .filter(|(_, parents)| **parents == 0)
+            .map(|(id, _)| (dag.sequence[*id], *id))
+            .collect();
+
+        let (job_tx, job_rx) = mpsc::channel::<&K>();
+        let job_rx = Mutex::new(job_rx);
+        let (done_tx, done_rx) = mpsc::channel::<(&K, Result<T, String>)>();
+        let workers = self.max_concurrency.min(dag.node_count());
+
+        thread::scope(|scope| {
+            for _ in 0..workers {
+                let done_tx = done_tx.clone();
+                let (job_rx, task) = (&job_rx, &task);
+                scope.spawn(move || {
+                    loop {
+                        // Its own statement, so the lock is released before running the task
+                        let job = job_rx.lock().unwrap().recv();
+                        let Ok(id) = job else {
+                            break;
+                        };
+                        let node = &dag.nodes[id];
+                        let result = panic::catch_unwind(AssertUnwindSafe(|| task(node)))
+                            .unwrap_or_else(|_| Err(format!("Task '{}' panicked", id)));
+                        if done_tx.send((id, result)).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+
+            let mut running = 0;
+            loop {
+                while running < workers && !self.cancel.is_cancelled() {
+                    let Some((_, id)) = ready.pop_first() else {
+                        break;
+                    };
+                    job_tx.send(id).unwrap();
+                    running += 1;
+                }
+                if running == 0 {
+                    break;
+                }
+
+                let (id, result) = done_rx.recv().unwrap();
+                running -= 1;
+                match result {
+                    Ok(value) => {
+                        outcomes.insert(id, TaskOutcome::Completed(value));
+                        for child in dag.edges[id].keys() {
+                            let parents = pending.get_mut(child).unwrap();
+                            *parents -= 1;
+                            if *parents == 0 {
+                                ready.insert(dag.sequence[child], child);
+                            }
+                        }
+                    }
+                    Err(error) => {
+                        outcomes.insert(id, TaskOutcome::Failed(error));
+                    }
+                }
+            }
+            drop(job_tx);
+        });
+
+        // Nodes never started: descendants of failures were skipped, the rest cancelled
+        let failed: Vec<&K> = outcomes
+            .iter()
+            .filter(|(_, outcome)| matches!(outcome, TaskOutcome::Failed(_)))
+            .map(|(id, _)| *id)
+            .collect();
+        let mut skipped: HashSet<&K> = HashSet::new();
+        for id in failed {
+            skipped.extend(dag.descendants::<K>(id).unwrap().into_iter().map(|n| &n.id));
+        }
+        for id in dag.nodes.keys() {
+            if !outcomes.contains_key(id) {
+                let outcome = if skipped.contains(id) {
+                    TaskOutcome::Skipped
+                } else {
+                    TaskOutcome::Cancelled
+                };
+                outcomes.insert(id, outcome);
+            }
+        }
+        outcomes
+            .into_iter()
+            .map(|(id, outcome)| (id.clone(), outcome))
+            .collect()
+    }
+}
+
+impl Default for Executor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::Node;
+
+    fn dag(nodes: &[&str], edges: &[(&str, &str)]) -> Dag {
+        let mut dag = Dag::new();
+        for id in nodes {
+            dag.add_node(Node::new(*id, format!("Task {}", id)))
+                .unwrap();
+        }
+        for (from, to) in edges {
+            dag.add_edge(*from, *to).unwrap();
+        }
+        dag
+    }
+
+    #[test]
+    fn test_runs_after_parents() {
+        let dag = dag(
+            &["A", "B", "C", "D"],
+            &[("A", "B"), ("A", "C"), ("B", "D"), ("C", "D")],
+        );
+        let finished = Mutex::new(Vec::new());
+        let outcomes = Executor::new().with_max_concurrency(4).run(&dag, |node| {
+            finished.lock().unwrap().push(node.id.clone());
+            Ok(node.data.len())
+        });
+
+        let finished = finished.into_inner().unwrap();
+        assert_eq!(finished.first().unwrap(), "A");
+        assert_eq!(finished.last().unwrap(), "D");
+        assert_eq!(outcomes.len(), 4);
+        assert_eq!(outcomes["D"], TaskOutcome::Completed(6));
+
+        let empty = Executor::new().run(&Dag::new(), |_| Ok(()));
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_failure_skips_descendants() {
+        let dag = dag(
+            &["A", "B", "C", "D", "E"],
+            &[("A", "B"), ("B", "C"), ("A", "D"), ("E", "C")],
+        );
+        let outcomes = Executor::new().run(&dag, |node| match node.id.as_str() {
+            "B" => Err("compile error".to_string()),
+            "E" => panic!("boom"),
+            _ => Ok(()),
+        });
+
+        assert_eq!(outcomes["A"], TaskOutcome::Completed(()));
+        assert_eq!(
+            outcomes["B"],
+            TaskOutcome::Failed("compile error".to_string())
+        );
+        assert_eq!(outcomes["C"], TaskOutcome::Skipped);
+        assert_eq!(outcomes["D"], TaskOutcome::Completed(()));
+        assert_eq!(
+            outcomes["E"],
+            TaskOutcome::Failed("Task 'E' panicked".to_string())
+        );
+    }
+
+    #[test]
+    fn test_max_concurrency() {
+        let ids: Vec<String> = (0..8).map(|i| format!("t{}", i)).collect();
+        let ids: Vec<&str> = ids.iter().map(String::as_str).collect();
+        let dag = dag(&ids, &[]);
+
+        for limit in [1, 3] {
+            let running = AtomicUsize::new(0);
+            let peak = AtomicUsize::new(0);
+            let outcomes = Executor::new().with_max_concurrency(limit).run(&dag, |_| {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                thread::sleep(Duration::from_millis(20));
+                running.fetch_sub(1, Ordering::SeqCst);
+                Ok(())
+            });
+            assert_eq!(outcomes.len(), 8);
+            assert!(peak.load(Ordering::SeqCst) <= limit);
+        }
+    }
+
+    #[test]
+    fn test_cancellation() {
+        let dag = dag(&["A", "B", "C", "D"], &[("A", "B"), ("B", "C")]);
+        let cancel = CancelToken::new();
+        let outcomes = Executor::new()
+            .with_max_concurrency(1)
+            .with_cancel_token(cancel.clone())
+            .run(&dag, |node| {
+                if node.id == "A" {
+                    cancel.cancel();
+                }
+                Ok(())
+            });
+
+        assert!(cancel.is_cancelled());
+        assert_eq!(outcomes["A"], TaskOutcome::Completed(()));
+        assert_eq!(outcomes["B"], TaskOutcome::Cancelled);
+        assert_eq!(outcomes["C"], TaskOutcome::Cancelled);
+        assert_eq!(outcomes["D"], TaskOutcome::Cancelled);
+    }
+}
@@ -1,16 +1,33 @@
+use std::borrow::Borrow;
 use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Display;
+use std::hash::Hash;
 
+mod algorithms;
 pub mod blockchain;
+mod executor;
+mod render;
+mod subgraph;
+mod topological;
 
-/// Represents a node in the DAG
+pub use algorithms::{CriticalPath, DagPath};
+pub use executor::{CancelToken, Executor, TaskOutcome};
+pub use subgraph::MergePolicy;
+pub use topological::TopologicalIter;
+
+/// Represents a node in the DAG, generic over its id and payload types
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct Node {
-    pub id: String,
-    pub data: String,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DagNode<K, V> {
+    pub id: K,
+    pub data: V,
 }
 
-impl Node {
-    pub fn new(id: impl Into<String>, data: impl Into<String>) -> Self {
+/// Node with a string id and payload
+pub type Node = DagNode<String, String>;
+
+impl<K, V> DagNode<K, V> {
+    pub fn new(id: impl Into<K>, data: impl Into<V>) -> Self {
         Self {
             id: id.into(),
             data: data.into(),
@@ -18,152 +35,504 @@ impl Node {
     }
 }
 
-/// Directed Acyclic Graph (DAG) structure
-#[derive(Debug)]
-pub struct Dag {
-    nodes: HashMap<String, Node>,
-    edges: HashMap<String, HashSet<String>>, // node_id -> set of child node_ids
+/// Weight of an edge, used by weighted graph algorithms
+pub trait EdgeWeight {
+    fn weight(&self) -> u64;
+}
+
+/// Edges without data count as one unit
+impl EdgeWeight for () {
+    fn weight(&self) -> u64 {
+        1
+    }
+}
+
+impl EdgeWeight for u64 {
+    fn weight(&self) -> u64 {
+        *self
+    }
+}
+
+impl EdgeWeight for u32 {
+    fn weight(&self) -> u64 {
+        *self as u64
+    }
+}
+
+/// Directed Acyclic Graph (DAG) structure, generic over the node id `K`, node payload `V`
+/// and edge payload `E`
+#[derive(Debug, Clone)]
+pub struct Dag<K = String, V = String, E = ()> {
+    nodes: HashMap<K, DagNode<K, V>>,
+    edges: HashMap<K, HashMap<K, E>>, // node_id -> child node_id -> edge data
+    parents: HashMap<K, HashSet<K>>,  // node_id -> parent node_ids
+    order: HashMap<K, usize>,         // node_id -> position in a topological order
+    sequence: HashMap<K, usize>,      // node_id -> insertion sequence number
+    next_sequence: usize,             // sequence number and initial position of the next node
 }
 
 impl Dag {
-    /// Create a new empty DAG
+    /// Create a new empty DAG with string ids and payloads
+    ///
+    /// Use `Dag::default()` for other id, payload and edge types.
     pub fn new() -> Self {
-        Self {
-            nodes: HashMap::new(),
-            edges: HashMap::new(),
-        }
+        Self::default()
     }
+}
 
+impl<K: Hash + Eq + Clone, V, E> Dag<K, V, E> {
     /// Add a node to the DAG
-    pub fn add_node(&mut self, node: Node) -> Result<(), String> {
+    pub fn add_node(&mut self, node: DagNode<K, V>) -> Result<(), String>
+    where
+        K: Display,
+    {
         if self.nodes.contains_key(&node.id) {
             return Err(format!("Node with id '{}' already exists", node.id));
         }
+        self.insert_node(node);
+        Ok(())
+    }
+
+    /// Insert a node whose id is not in the DAG yet
+    fn insert_node(&mut self, node: DagNode<K, V>) {
         let node_id = node.id.clone();
         self.nodes.insert(node_id.clone(), node);
-        self.edges.insert(node_id, HashSet::new());
-        Ok(())
+        self.edges.insert(node_id.clone(), HashMap::new());
+        self.parents.insert(node_id.clone(), HashSet::new());
+        self.order.insert(node_id.clone(), self.next_sequence);
+        self.sequence.insert(node_id, self.next_sequence);
+        self.next_sequence += 1;
     }
 
-    /// Add an edge from one node to another
+    /// Add an edge from one node to another, with default edge data
     /// Returns an error if adding the edge would create a cycle
-    pub fn add_edge(&mut self, from: &str, to: &str) -> Result<(), String> {
-        if !self.nodes.contains_key(from) {
+    pub fn add_edge<Q>(&mut self, from: &Q, to: &Q) -> Result<(), String>
+    where
+        K: Borrow<Q> + Display,
+        Q: Hash + Eq + Display + ?Sized,
+        E: Default,
+    {
+        self.add_edge_with(from, to, E::default())
+    }
+
+    /// Add an edge carrying data (weight, label, dependency kind, ...)
+    ///
+    /// Adding an existing edge replaces its data.
+    pub fn add_edge_with<Q>(&mut self, from: &Q, to: &Q, data: E) -> Result<(), String>
+    where
+        K: Borrow<Q> + Display,
+        Q: Hash + Eq + Display + ?Sized,
+    {
+        let Some((from_id, _)) = self.nodes.get_key_value(from) else {
             return Err(format!("Source node '{}' does not exist", from));
-        }
-        if !self.nodes.contains_key(to) {
+        };
+        let Some((to_id, _)) = self.nodes.get_key_value(to) else {
             return Err(format!("Target node '{}' does not exist", to));
-        }
+        };
+        let (from_id, to_id) = (from_id.clone(), to_id.clone());
 
         // Check if adding this edge would create a cycle
-        if self.would_create_cycle(from, to) {
+        if let Err(path) = self.reorder_for_edge(&from_id, &to_id) {
             return Err(format!(
-                "Adding edge from '{}' to '{}' would create a cycle",
-                from, to
+                "Adding edge from '{}' to '{}' would create a cycle: '{}' already reaches '{}' via {}",
+                from,
+                to,
+                to,
+                from,
+                format_path(&path)
             ));
         }
 
-        self.edges.get_mut(from).unwrap().insert(to.to_string());
+        self.parents.get_mut(to).unwrap().insert(from_id);
+        self.edges.get_mut(from).unwrap().insert(to_id, data);
         Ok(())
     }
 
-    /// Check if adding an edge would create a cycle using DFS
-    fn would_create_cycle(&self, from: &str, to: &str) -> bool {
-        // If there's already a path from 'to' to 'from', adding 'from' -> 'to' creates a cycle
-        self.has_path(to, from)
+    /// Add many edges at once, validated with a single topological sort
+    ///
+    /// Faster than repeated `add_edge_with` when loading large graphs. Either all edges are
+    /// added or, if a node is missing or the edges would create a cycle, none are.
+    pub fn add_edges_batch<I>(&mut self, edges: I) -> Result<(), String>
+    where
+        I: IntoIterator<Item = (K, K, E)>,
+        K: Display,
+    {
+        let edges: Vec<(K, K, E)> = edges.into_iter().collect();
+        let count = edges.len();
+        self.insert_edges_batch(edges).map_err(|error| match error {
+            BatchError::MissingSource(id) => format!("Source node '{}' does not exist", id),
+            BatchError::MissingTarget(id) => format!("Target node '{}' does not exist", id),
+            BatchError::Cycle(cycle) => format!(
+                "Adding the batch of {} edges would create a cycle: {}",
+                count,
+                format_path(&cycle)
+            ),
+        })
     }
 
-    /// Check if there's a path from start to end using BFS
-    fn has_path(&self, start: &str, end: &str) -> bool {
-        if start == end {
-            return true;
+    /// Add a batch of edges, reporting a rejected batch without formatting node ids
+    fn insert_edges_batch(&mut self, edges: Vec<(K, K, E)>) -> Result<(), BatchError<K>> {
+        let mut new_children: HashMap<&K, Vec<&K>> = HashMap::new();
+        for (from, to, _) in &edges {
+            if !self.nodes.contains_key(from) {
+                return Err(BatchError::MissingSource(from.clone()));
+            }
+            if !self.nodes.contains_key(to) {
+                return Err(BatchError::MissingTarget(to.clone()));
+            }
+            if !self.edges[from].contains_key(to) {
+                new_children.entry(from).or_default().push(to);
+            }
         }
 
-        let mut visited = HashSet::new();
-        let mut queue = VecDeque::new();
-        queue.push_back(start);
-        visited.insert(start);
+        // Kahn's algorithm over existing and new edges
+        let mut in_degree: HashMap<&K, usize> = self
+            .parents
+            .iter()
+            .map(|(id, parents)| (id, parents.len()))
+            .collect();
+        for to in new_children.values().flatten() {
+            *in_degree.get_mut(to).unwrap() += 1;
+        }
+        let mut queue: VecDeque<&K> = in_degree
+            .iter()
+            .filter(|(_, degree)| **degree == 0)
+            .map(|(id, _)| *id)
+            .collect();
+        let mut sorted = Vec::with_capacity(self.nodes.len());
+        while let Some(id) = queue.pop_front() {
+            sorted.push(id);
+            let added = new_children.get(id).into_iter().flatten().copied();
+            for child in self.edges[id].keys().chain(added) {
+                let degree = in_degree.get_mut(child).unwrap();
+                *degree -= 1;
+                if *degree == 0 {
+                    queue.push_back(child);
+                }
+            }
+        }
+        if sorted.len() != self.nodes.len() {
+            // New edges first, then existing ones in topological order, for a stable report
+            let mut existing: Vec<(&K, &K, &E)> = self.edges().collect();
+            existing.sort_by_key(|(from, to, _)| (self.order[*from], self.order[*to]));
+            let all_edges = edges
+                .iter()
+                .map(|(from, to, _)| (from, to))
+                .chain(existing.into_iter().map(|(from, to, _)| (from, to)))
+                .map(|(from, to)| (from.clone(), to.clone()));
+            let mut cycle = find_cycle(all_edges).unwrap();
+            cycle.push(cycle[0].clone());
+            return Err(BatchError::Cycle(cycle));
+        }
 
-        while let Some(current) = queue.pop_front() {
-            if let Some(children) = self.edges.get(current) {
-                for child in children {
-                    if child == end {
-                        return true;
-                    }
-                    if !visited.contains(child.as_str()) {
-                        visited.insert(child.as_str());
-                        queue.push_back(child);
-                    }
+        let order: HashMap<K, usize> = sorted
+            .into_iter()
+            .enumerate()
+            .map(|(position, id)| (id.clone(), position))
+            .collect();
+        // Positions stay below `next_sequence`, so new nodes can still go last
+        self.order = order;
+        for (from, to, data) in edges {
+            self.parents.get_mut(&to).unwrap().insert(from.clone());
+            self.edges.get_mut(&from).unwrap().insert(to, data);
+        }
+        Ok(())
+    }
+
+    /// Update the topological order for a new edge `from -> to` (Pearce–Kelly)
+    ///
+    /// Only nodes ordered between `to` and `from` are visited. If the edge would create a
+    /// cycle the order is left untouched and the existing path from `to` to `from` returned.
+    fn reorder_for_edge(&mut self, from: &K, to: &K) -> Result<(), Vec<K>> {
+        let lower = self.order[to];
+        let upper = self.order[from];
+        if lower > upper {
+            return Ok(());
+        }
+
+        // Nodes reachable from `to` that are ordered before `from`
+        let mut forward = Vec::new();
+        let mut visited = HashSet::from([to]);
+        let mut reached_from: HashMap<&K, &K> = HashMap::new();
+        let mut stack = vec![to];
+        while let Some(id) = stack.pop() {
+            if id == from {
+                let mut path = vec![id.clone()];
+                let mut current = id;
+                while let Some(previous) = reached_from.get(current) {
+                    path.push((*previous).clone());
+                    current = previous;
+                }
+                path.reverse();
+                return Err(path);
+            }
+            forward.push(id);
+            for child in self.edges[id].keys() {
+                if self.order[child] <= upper && visited.insert(child) {
+                    reached_from.insert(child, id);
+                    stack.push(child);
+                }
+            }
+        }
+
+        // Nodes reaching `from` that are ordered after `to`
+        let mut backward = Vec::new();
+        let mut stack = vec![from];
+        visited.insert(from);
+        while let Some(id) = stack.pop() {
+            backward.push(id);
+            for parent in &self.parents[id] {
+                if self.order[parent] > lower && visited.insert(parent) {
+                    stack.push(parent);
+                }
+            }
+        }
+
+        // Reuse their positions: ancestors of `from` first, then descendants of `to`
+        forward.sort_by_key(|id| self.order[*id]);
+        backward.sort_by_key(|id| self.order[*id]);
+        let mut positions: Vec<usize> = backward
+            .iter()
+            .chain(&forward)
+            .map(|id| self.order[*id])
+            .collect();
+        positions.sort_unstable();
+        let moved: Vec<(K, usize)> = backward
+            .into_iter()
+            .chain(forward)
+            .cloned()
+            .zip(positions)
+            .collect();
+        self.order.extend(moved);
+        Ok(())
+    }
+
+    /// Remove the edge from one node to another, returning its data
+    pub fn remove_edge<Q>(&mut self, from: &Q, to: &Q) -> Option<E>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let data = self.edges.get_mut(from)?.remove(to)?;
+        self.parents.get_mut(to).unwrap().remove(from);
+        Some(data)
+    }
+
+    /// Remove a node together with its incoming and outgoing edges
+    pub fn remove_node<Q>(&mut self, node_id: &Q) -> Option<DagNode<K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let node = self.nodes.remove(node_id)?;
+        self.order.remove(node_id);
+        self.sequence.remove(node_id);
+        for child in self.edges.remove(node_id).unwrap().keys() {
+            self.parents.get_mut::<K>(child).unwrap().remove(node_id);
+        }
+        for parent in self.parents.remove(node_id).unwrap() {
+            self.edges.get_mut::<K>(&parent).unwrap().remove(node_id);
+        }
+        Some(node)
+    }
+
+    /// Remove a node and all of its descendants
+    ///
+    /// Returns the removed nodes, the given node first. Empty if the node does not exist.
+    pub fn remove_node_cascade<Q>(&mut self, node_id: &Q) -> Vec<DagNode<K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let Some((start, _)) = self.nodes.get_key_value(node_id) else {
+            return Vec::new();
+        };
+
+        let mut order = vec![start.clone()];
+        let mut visited: HashSet<K> = order.iter().cloned().collect();
+        let mut i = 0;
+        while i < order.len() {
+            for child in self.edges.get::<K>(&order[i]).unwrap().keys() {
+                if visited.insert(child.clone()) {
+                    order.push(child.clone());
                 }
             }
+            i += 1;
         }
 
-        false
+        let removed = order
+            .iter()
+            .map(|id| self.nodes.remove::<K>(id).unwrap())
+            .collect();
+        self.drop_dangling_edges();
+        removed
+    }
+
+    /// Keep only the nodes matching the predicate, dropping every edge of removed nodes
+    pub fn retain<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(&DagNode<K, V>) -> bool,
+    {
+        self.nodes.retain(|_, node| keep(node));
+        self.drop_dangling_edges();
+    }
+
+    /// Remove edges from or to nodes that no longer exist
+    fn drop_dangling_edges(&mut self) {
+        let nodes = &self.nodes;
+        self.edges.retain(|id, _| nodes.contains_key(id));
+        self.parents.retain(|id, _| nodes.contains_key(id));
+        self.order.retain(|id, _| nodes.contains_key(id));
+        self.sequence.retain(|id, _| nodes.contains_key(id));
+        for children in self.edges.values_mut() {
+            children.retain(|id, _| nodes.contains_key(id));
+        }
+        for parents in self.parents.values_mut() {
+            parents.retain(|id| nodes.contains_key(id));
+        }
+    }
+
+    /// Get the data of the edge from one node to another
+    pub fn get_edge<Q>(&self, from: &Q, to: &Q) -> Option<&E>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.edges.get(from)?.get(to)
+    }
+
+    /// Get mutable access to the data of an edge
+    pub fn get_edge_mut<Q>(&mut self, from: &Q, to: &Q) -> Option<&mut E>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.edges.get_mut(from)?.get_mut(to)
+    }
+
+    /// Iterate over all edges as `(from, to, data)`, in no particular order
+    pub fn edges(&self) -> impl Iterator<Item = (&K, &K, &E)> {
+        self.edges
+            .iter()
+            .flat_map(|(from, children)| children.iter().map(move |(to, data)| (from, to, data)))
+    }
+
+    /// Iterate over the outgoing edges of a node as `(to, data)`
+    pub fn outgoing_edges<Q>(&self, node_id: &Q) -> impl Iterator<Item = (&K, &E)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.edges.get(node_id).into_iter().flatten()
+    }
+
+    /// Nodes in insertion order
+    fn nodes_by_sequence(&self) -> Vec<&DagNode<K, V>> {
+        let mut nodes: Vec<&DagNode<K, V>> = self.nodes.values().collect();
+        nodes.sort_by_key(|node| self.sequence[&node.id]);
+        nodes
     }
 
     /// Get all nodes in the DAG
-    pub fn get_nodes(&self) -> Vec<&Node> {
+    pub fn get_nodes(&self) -> Vec<&DagNode<K, V>> {
         self.nodes.values().collect()
     }
 
     /// Get children of a node
-    pub fn get_children(&self, node_id: &str) -> Option<Vec<&Node>> {
+    pub fn get_children<Q>(&self, node_id: &Q) -> Option<Vec<&DagNode<K, V>>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
         self.edges.get(node_id).map(|children| {
             children
-                .iter()
-                .filter_map(|id| self.nodes.get(id))
+                .keys()
+                .filter_map(|id| self.nodes.get::<K>(id))
                 .collect()
         })
     }
 
-    /// Perform topological sort on the DAG
-    /// Returns nodes in topological order (dependencies before dependents)
-    pub fn topological_sort(&self) -> Result<Vec<Node>, String> {
-        let mut in_degree: HashMap<String, usize> = HashMap::new();
-
-        // Initialize in-degree for all nodes
-        for node_id in self.nodes.keys() {
-            in_degree.insert(node_id.clone(), 0);
-        }
+    /// Get parents of a node
+    pub fn get_parents<Q>(&self, node_id: &Q) -> Option<Vec<&DagNode<K, V>>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.parents.get(node_id).map(|parents| {
+            parents
+                .iter()
+                .filter_map(|id| self.nodes.get::<K>(id))
+                .collect()
+        })
+    }
 
-        // Calculate in-degree for each node
-        for children in self.edges.values() {
-            for child in children {
-                *in_degree.get_mut(child).unwrap() += 1;
-            }
-        }
+    /// Get all nodes the given node is reachable from, in breadth-first order
+    pub fn ancestors<Q>(&self, node_id: &Q) -> Option<Vec<&DagNode<K, V>>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let (start, _) = self.nodes.get_key_value(node_id)?;
+        Some(self.reachable(start, |id| self.parents.get::<K>(id).unwrap().iter()))
+    }
 
-        // Queue nodes with in-degree 0
-        let mut queue = VecDeque::new();
-        for (node_id, &degree) in &in_degree {
-            if degree == 0 {
-                queue.push_back(node_id.clone());
-            }
-        }
+    /// Get all nodes reachable from the given node, in breadth-first order
+    pub fn descendants<Q>(&self, node_id: &Q) -> Option<Vec<&DagNode<K, V>>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let (start, _) = self.nodes.get_key_value(node_id)?;
+        Some(self.reachable(start, |id| self.edges.get::<K>(id).unwrap().keys()))
+    }
 
+    /// Breadth-first walk from `start` along `next`, excluding `start`
+    fn reachable<'a, I>(&'a self, start: &'a K, next: impl Fn(&'a K) -> I) -> Vec<&'a DagNode<K, V>>
+    where
+        I: Iterator<Item = &'a K>,
+    {
+        let mut visited = HashSet::from([start]);
+        let mut queue = VecDeque::from([start]);
         let mut result = Vec::new();
-
-        while let Some(node_id) = queue.pop_front() {
-            result.push(self.nodes.get(&node_id).unwrap().clone());
-
-            // Reduce in-degree of children
-            if let Some(children) = self.edges.get(&node_id) {
-                for child in children {
-                    let degree = in_degree.get_mut(child).unwrap();
-                    *degree -= 1;
-                    if *degree == 0 {
-                        queue.push_back(child.clone());
-                    }
+        while let Some(current) = queue.pop_front() {
+            for id in next(current) {
+                if visited.insert(id) {
+                    result.push(&self.nodes[id]);
+                    queue.push_back(id);
                 }
             }
         }
+        result
+    }
 
-        // If not all nodes are in result, there's a cycle (shouldn't happen with our validation)
-        if result.len() != self.nodes.len() {
-            return Err("DAG contains a cycle".to_string());
-        }
+    /// Get nodes without parents
+    pub fn roots(&self) -> Vec<&DagNode<K, V>> {
+        self.parents
+            .iter()
+            .filter(|(_, parents)| parents.is_empty())
+            .map(|(id, _)| &self.nodes[id])
+            .collect()
+    }
+
+    /// Get nodes without children
+    pub fn leaves(&self) -> Vec<&DagNode<K, V>> {
+        self.edges
+            .iter()
+            .filter(|(_, children)| children.is_empty())
+            .map(|(id, _)| &self.nodes[id])
+            .collect()
+    }
 
-        Ok(result)
+    /// Perform topological sort on the DAG
+    /// Returns nodes in topological order (dependencies before dependents)
+    ///
+    /// The order is deterministic: among independent nodes, the earliest added comes first.
+    /// Use `topological_iter` to avoid cloning the nodes.
+    pub fn topological_sort(&self) -> Result<Vec<DagNode<K, V>>, String>
+    where
+        V: Clone,
+    {
+        Ok(self.topological_iter().cloned().collect())
     }
 
     /// Get the number of nodes in the DAG
@@ -177,9 +546,148 @@ impl Dag {
     }
 }
 
-impl Default for Dag {
+impl<K: Ord, V, E> Dag<K, V, E> {
+    /// Nodes sorted by id, for deterministic output
+    fn sorted_nodes(&self) -> Vec<&DagNode<K, V>> {
+        let mut nodes: Vec<&DagNode<K, V>> = self.nodes.values().collect();
+        nodes.sort_by(|a, b| a.id.cmp(&b.id));
+        nodes
+    }
+
+    /// Edges as `(from, to, data)` sorted by ids, for deterministic output
+    fn sorted_edges(&self) -> Vec<(&K, &K, &E)> {
+        let mut edges: Vec<(&K, &K, &E)> = self
+            .edges
+            .iter()
+            .flat_map(|(from, children)| children.iter().map(move |(to, data)| (from, to, data)))
+            .collect();
+        edges.sort_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)));
+        edges
+    }
+}
+
+/// Find a cycle in a list of directed edges, e.g. before importing them into a `Dag`
+///
+/// Returns the nodes of the first cycle found, each with an edge to the next and the last
+/// with an edge back to the first, or `None` if the edges are acyclic. Nodes and edges are
+/// explored in list order, so the result is deterministic.
+pub fn find_cycle<K, I>(edges: I) -> Option<Vec<K>>
+where
+    K: Hash + Eq + Clone,
+    I: IntoIterator<Item = (K, K)>,
+{
+    let mut nodes: Vec<K> = Vec::new();
+    let mut children: HashMap<K, Vec<K>> = HashMap::new();
+    for (from, to) in edges {
+        for id in [&from, &to] {
+            if !children.contains_key(id) {
+                nodes.push(id.clone());
+                children.insert(id.clone(), Vec::new());
+            }
+        }
+        children.get_mut(&from).unwrap().push(to);
+    }
+
+    // Iterative depth-first search; a child already on the stack closes a cycle
+    let mut done: HashSet<&K> = HashSet::new();
+    for root in &nodes {
+        if done.contains(root) {
+            continue;
+        }
+        let mut stack: Vec<(&K, usize)> = vec![(root, 0)];
+        let mut on_stack: HashSet<&K> = HashSet::from([root]);
+        while let Some((id, next)) = stack.last_mut() {
+            let Some(child) = children[*id].get(*next) else {
+                on_stack.remove(*id);
+                done.insert(*id);
+                stack.pop();
+                continue;
+            };
+            *next += 1;
+            if on_stack.contains(child) {
+                let start = stack.iter().position(|(id, _)| *id == child).unwrap();
+                return Some(stack[start..].iter().map(|(id, _)| (*id).clone()).collect());
+            }
+            if !done.contains(child) {
+                on_stack.insert(child);
+                stack.push((child, 0));
+            }
+        }
+    }
+    None
+}
+
+/// Reason `Dag::insert_edges_batch` rejected a batch
+enum BatchError<K> {
+    MissingSource(K),
+    MissingTarget(K),
+    Cycle(Vec<K>), // Closed path, first node repeated last
+}
+
+/// Format a path of node ids as `A -> B -> C`
+fn format_path<K: Display>(path: &[K]) -> String {
+    path.iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<_>>()
+        .join(" -> ")
+}
+
+impl<K, V, E> Default for Dag<K, V, E> {
     fn default() -> Self {
-        Self::new()
+        Self {
+            nodes: HashMap::new(),
+            edges: HashMap::new(),
+            parents: HashMap::new(),
+            order: HashMap::new(),
+            sequence: HashMap::new(),
+            next_sequence: 0,
+        }
+    }
+}
+
+/// Serialized form of a `Dag`: nodes sorted by id and edges as sorted `(from, to, data)`
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct DagData<K, V, E> {
+    nodes: Vec<DagNode<K, V>>,
+    edges: Vec<(K, K, E)>,
+}
+
+#[cfg(feature = "serde")]
+impl<K, V, E> serde::Serialize for Dag<K, V, E>
+where
+    K: serde::Serialize + Ord + Clone,
+    V: serde::Serialize + Clone,
+    E: serde::Serialize + Clone,
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let nodes = self.sorted_nodes().into_iter().cloned().collect();
+        let edges = self
+            .sorted_edges()
+            .into_iter()
+            .map(|(from, to, data)| (from.clone(), to.clone(), data.clone()))
+            .collect();
+        DagData { nodes, edges }.serialize(serializer)
+    }
+}
+
+/// Deserialization rebuilds the DAG, so duplicate nodes, dangling edges and cycles are rejected
+#[cfg(feature = "serde")]
+impl<'de, K, V, E> serde::Deserialize<'de> for Dag<K, V, E>
+where
+    K: serde::Deserialize<'de> + Hash + Eq + Clone + Display,
+    V: serde::Deserialize<'de>,
+    E: serde::Deserialize<'de>,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = DagData::<K, V, E>::deserialize(deserializer)?;
+        let mut dag = Dag::default();
+        for node in data.nodes {
+            dag.add_node(node).map_err(serde::de::Error::custom)?;
+        }
+        dag.add_edges_batch(data.edges)
+            .map_err(serde::de::Error::custom)?;
+        Ok(dag)
     }
 }
 
@@ -256,6 +764,49 @@ mod tests {
         assert!(dag.add_edge("C", "A").is_err());
     }
 
+    #[test]
+    fn test_cycle_error_reports_path() {
+        let mut dag = diamond();
+        dag.add_node(Node::new("E", "")).unwrap();
+        dag.add_edge("D", "E").unwrap();
+        assert_eq!(
+            dag.add_edge("E", "B").unwrap_err(),
+            "Adding edge from 'E' to 'B' would create a cycle: 'B' already reaches 'E' via B -> D -> E"
+        );
+        assert_eq!(
+            dag.add_edge("A", "A").unwrap_err(),
+            "Adding edge from 'A' to 'A' would create a cycle: 'A' already reaches 'A' via A"
+        );
+
+        let edge = |from: &str, to: &str| (from.to_string(), to.to_string(), ());
+        assert_eq!(
+            dag.add_edges_batch(vec![edge("E", "C")]).unwrap_err(),
+            "Adding the batch of 1 edges would create a cycle: E -> C -> D -> E"
+        );
+    }
+
+    #[test]
+    fn test_find_cycle() {
+        let edges = |list: &[(&'static str, &'static str)]| list.to_vec();
+        assert_eq!(
+            find_cycle(edges(&[("A", "B"), ("B", "C"), ("A", "C")])),
+            None
+        );
+        assert_eq!(find_cycle(Vec::<(u32, u32)>::new()), None);
+        assert_eq!(
+            find_cycle(edges(&[("A", "B"), ("B", "C"), ("C", "D"), ("D", "B")])),
+            Some(vec!["B", "C", "D"])
+        );
+        assert_eq!(
+            find_cycle(edges(&[("X", "Y"), ("Y", "Y")])),
+            Some(vec!["Y"])
+        );
+        assert_eq!(
+            find_cycle(vec![(1, 2), (3, 1), (2, 3)]),
+            Some(vec![1, 2, 3])
+        );
+    }
+
     #[test]
     fn test_topological_sort_linear() {
         let mut dag = Dag::new();
@@ -366,4 +917,354 @@ mod tests {
         let nodes = dag.get_nodes();
         assert_eq!(nodes.len(), 2);
     }
+
+    #[test]
+    fn test_generic_ids_and_payloads() {
+        #[derive(Debug, Clone, PartialEq)]
+        struct Stage {
+            name: &'static str,
+            cost: u32,
+        }
+
+        let mut dag: Dag<u32, Stage> = Dag::default();
+        dag.add_node(DagNode::new(
+            1u32,
+            Stage {
+                name: "fetch",
+                cost: 3,
+            },
+        ))
+        .unwrap();
+        dag.add_node(DagNode::new(
+            2u32,
+            Stage {
+                name: "build",
+                cost: 10,
+            },
+        ))
+        .unwrap();
+        dag.add_node(DagNode::new(
+            3u32,
+            Stage {
+                name: "test",
+                cost: 5,
+            },
+        ))
+        .unwrap();
+        dag.add_edge(&1, &2).unwrap();
+        dag.add_edge(&2, &3).unwrap();
+
+        assert_eq!(
+            dag.add_edge(&3, &1).unwrap_err(),
+            "Adding edge from '3' to '1' would create a cycle: '1' already reaches '3' via 1 -> 2 -> 3"
+        );
+        assert!(dag.add_edge(&1, &4).is_err());
+
+        let order: Vec<&str> = dag
+            .topological_sort()
+            .unwrap()
+            .iter()
+            .map(|node| node.data.name)
+            .collect();
+        assert_eq!(order, ["fetch", "build", "test"]);
+        assert_eq!(dag.get_children(&1).unwrap()[0].data.cost, 10);
+
+        // Queries and subgraphs need no `Display` ids, only building errors do
+        fn inner_nodes<K: Hash + Eq + Clone, V: Clone, E: Clone>(dag: &Dag<K, V, E>) -> usize {
+            dag.induced_subgraph(|node| !dag.get_parents(&node.id).unwrap().is_empty())
+                .topological_iter()
+                .count()
+        }
+        assert_eq!(inner_nodes(&dag), 2);
+    }
+
+    fn diamond() -> Dag {
+        let mut dag = Dag::new();
+        for id in ["A", "B", "C", "D"] {
+            dag.add_node(Node::new(id, format!("Node {}", id))).unwrap();
+        }
+        dag.add_edge("A", "B").unwrap();
+        dag.add_edge("A", "C").unwrap();
+        dag.add_edge("B", "D").unwrap();
+        dag.add_edge("C", "D").unwrap();
+        dag
+    }
+
+    #[test]
+    fn test_remove_edge() {
+        let mut dag = diamond();
+        assert_eq!(dag.remove_edge("A", "B"), Some(()));
+        assert_eq!(dag.remove_edge("A", "B"), None);
+        assert_eq!(dag.remove_edge("X", "B"), None);
+        assert_eq!(dag.edge_count(), 3);
+        // The edge can be added back in the other direction
+        dag.add_edge("B", "A").unwrap();
+    }
+
+    #[test]
+    fn test_remove_node() {
+        let mut dag = diamond();
+        let removed = dag.remove_node("B").unwrap();
+        assert_eq!(removed.data, "Node B");
+        assert!(dag.remove_node("B").is_none());
+        assert_eq!(dag.node_count(), 3);
+        assert_eq!(dag.edge_count(), 2);
+        assert_eq!(dag.get_children("A").unwrap().len(), 1);
+        assert!(dag.get_edge("B", "D").is_none());
+
+        // The id can be reused
+        dag.add_node(Node::new("B", "new")).unwrap();
+        assert_eq!(dag.get_children("B").unwrap().len(), 0);
+        assert_eq!(dag.topological_sort().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_remove_node_cascade() {
+        let mut dag = diamond();
+        dag.add_node(Node::new("E", "")).unwrap();
+        dag.add_edge("E", "D").unwrap();
+
+        let removed: Vec<String> = dag
+            .remove_node_cascade("B")
+            .into_iter()
+            .map(|node| node.id)
+            .collect();
+        assert_eq!(removed, ["B", "D"]);
+        assert_eq!(dag.node_count(), 3);
+        assert_eq!(dag.edge_count(), 1);
+        assert_eq!(dag.get_children("E").unwrap().len(), 0);
+        assert!(dag.remove_node_cascade("missing").is_empty());
+
+        assert_eq!(diamond().remove_node_cascade("A").len(), 4);
+    }
+
+    #[test]
+    fn test_retain() {
+        let mut dag = diamond();
+        dag.retain(|node| node.id != "A" && node.id != "D");
+        assert_eq!(dag.node_count(), 2);
+        assert_eq!(dag.edge_count(), 0);
+        assert_eq!(dag.edges().count(), 0);
+
+        let mut dag = diamond();
+        dag.retain(|_| true);
+        assert_eq!(dag.edge_count(), 4);
+    }
+
+    fn sorted_ids(nodes: Option<Vec<&Node>>) -> Vec<&str> {
+        let mut ids: Vec<&str> = nodes.unwrap().iter().map(|node| node.id.as_str()).collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn test_get_parents() {
+        let mut dag = diamond();
+        assert_eq!(sorted_ids(dag.get_parents("D")), ["B", "C"]);
+        assert!(dag.get_parents("A").unwrap().is_empty());
+        assert!(dag.get_parents("missing").is_none());
+
+        dag.remove_edge("B", "D");
+        assert_eq!(sorted_ids(dag.get_parents("D")), ["C"]);
+        dag.remove_node("C");
+        assert!(dag.get_parents("D").unwrap().is_empty());
+        dag.retain(|node| node.id != "A");
+        assert!(dag.get_parents("B").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_ancestors_and_descendants() {
+        let mut dag = diamond();
+        dag.add_node(Node::new("E", "")).unwrap();
+        dag.add_edge("D", "E").unwrap();
+
+        assert_eq!(sorted_ids(dag.ancestors("E")), ["A", "B", "C", "D"]);
+        assert_eq!(sorted_ids(dag.ancestors("B")), ["A"]);
+        assert!(dag.ancestors("A").unwrap().is_empty());
+        assert_eq!(sorted_ids(dag.descendants("B")), ["D", "E"]);
+        assert_eq!(dag.descendants("A").unwrap().len(), 4);
+        assert!(dag.descendants("missing").is_none());
+
+        // Breadth-first: closer nodes come first
+        let order: Vec<&str> = dag
+            .descendants("A")
+            .unwrap()
+            .iter()
+            .map(|node| node.id.as_str())
+            .collect();
+        assert_eq!(&order[2..], ["D", "E"]);
+    }
+
+    #[test]
+    fn test_roots_and_leaves() {
+        let mut dag = diamond();
+        dag.add_node(Node::new("E", "")).unwrap();
+        assert_eq!(sorted_ids(Some(dag.roots())), ["A", "E"]);
+        assert_eq!(sorted_ids(Some(dag.leaves())), ["D", "E"]);
+
+        dag.remove_node("A");
+        assert_eq!(sorted_ids(Some(dag.roots())), ["B", "C", "E"]);
+        dag.remove_node_cascade("B");
+        assert_eq!(sorted_ids(Some(dag.leaves())), ["C", "E"]);
+        assert!(Dag::new().roots().is_empty());
+    }
+
+    /// Check that every edge goes forward in the maintained topological order
+    fn assert_order_consistent<K: Hash + Eq + Clone + Display, V, E>(dag: &Dag<K, V, E>) {
+        assert_eq!(dag.order.len(), dag.nodes.len());
+        for (from, to, _) in dag.edges() {
+            assert!(
+                dag.order[from] < dag.order[to],
+                "edge {} -> {} goes backwards",
+                from,
+                to
+            );
+        }
+    }
+
+    #[test]
+    fn test_incremental_order_matches_reachability() {
+        let mut dag: Dag<u32, ()> = Dag::default();
+        for id in 0..40u32 {
+            dag.add_node(DagNode::new(id, ())).unwrap();
+        }
+
+        // Deterministic pseudo-random edges, checked against a plain reachability search
+        let mut seed = 12_345u64;
+        let mut next = |bound: u64| {
+            seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+            ((seed >> 33) % bound) as u32
+        };
+        for step in 0..600 {
+            let (from, to) = (next(40), next(40));
+            let reachable = from == to
+                || dag
+                    .descendants(&to)
+                    .unwrap()
+                    .iter()
+                    .any(|node| node.id == from);
+            assert_eq!(dag.add_edge(&from, &to).is_err(), reachable);
+            if step % 50 == 49 {
+                // Re-added nodes go to the end of the order, leaving a gap behind
+                let id = next(40);
+                dag.remove_node(&id).unwrap();
+                dag.add_node(DagNode::new(id, ())).unwrap();
+            }
+            assert_order_consistent(&dag);
+        }
+        assert_eq!(dag.topological_sort().unwrap().len(), dag.node_count());
+    }
+
+    #[test]
+    fn test_incremental_order_reverse_chain() {
+        // Adding a chain back to front reorders on every edge
+        let mut dag: Dag<u32, ()> = Dag::default();
+        for id in 0..200u32 {
+            dag.add_node(DagNode::new(id, ())).unwrap();
+        }
+        for id in (1..200u32).rev() {
+            dag.add_edge(&(id - 1), &id).unwrap();
+        }
+        assert_order_consistent(&dag);
+        assert!(dag.add_edge(&199, &0).is_err());
+        assert!(dag.add_edge(&5, &5).is_err());
+    }
+
+    #[test]
+    fn test_add_edges_batch() {
+        let mut dag = Dag::new();
+        for id in ["A", "B", "C", "D"] {
+            dag.add_node(Node::new(id, "")).unwrap();
+        }
+        dag.add_edge("C", "D").unwrap();
+        let edge = |from: &str, to: &str| (from.to_string(), to.to_string(), ());
+
+        dag.add_edges_batch(vec![edge("D", "A"), edge("A", "B"), edge("B", "C")])
+            .unwrap_err();
+        assert_eq!(dag.edge_count(), 1);
+        assert_eq!(
+            dag.add_edges_batch(vec![edge("A", "B"), edge("A", "X")])
+                .unwrap_err(),
+            "Target node 'X' does not exist"
+        );
+        assert_eq!(dag.edge_count(), 1);
+
+        dag.add_edges_batch(vec![edge("B", "C"), edge("A", "B"), edge("C", "D")])
+            .unwrap();
+        assert_eq!(dag.edge_count(), 3);
+        assert_eq!(sorted_ids(dag.get_parents("C")), ["B"]);
+        assert_order_consistent(&dag);
+
+        // Incremental checks keep working on the rebuilt order
+        assert!(dag.add_edge("D", "A").is_err());
+        dag.add_node(Node::new("E", "")).unwrap();
+        dag.add_edge("E", "A").unwrap();
+        assert_order_consistent(&dag);
+    }
+
+    #[test]
+    fn test_edge_payloads() {
+        #[derive(Debug, Clone, PartialEq)]
+        enum Dependency {
+            Build,
+            Runtime,
+        }
+
+        let mut dag: Dag<String, String, Dependency> = Dag::default();
+        for id in ["A", "B", "C"] {
+            dag.add_node(Node::new(id, "")).unwrap();
+        }
+        dag.add_edge_with("A", "B", Dependency::Build).unwrap();
+        dag.add_edge_with("A", "C", Dependency::Runtime).unwrap();
+        assert!(dag.add_edge_with("C", "A", Dependency::Build).is_err());
+
+        assert_eq!(dag.get_edge("A", "B"), Some(&Dependency::Build));
+        assert_eq!(dag.get_edge("B", "A"), None);
+        *dag.get_edge_mut("A", "B").unwrap() = Dependency::Runtime;
+        assert_eq!(dag.get_edge("A", "B"), Some(&Dependency::Runtime));
+
+        // Re-adding an edge replaces its data
+        dag.add_edge_with("A", "C", Dependency::Build).unwrap();
+        assert_eq!(dag.edge_count(), 2);
+
+        let mut outgoing: Vec<(&String, &Dependency)> = dag.outgoing_edges("A").collect();
+        outgoing.sort_by(|a, b| a.0.cmp(b.0));
+        assert_eq!(
+            outgoing,
+            [
+                (&"B".to_string(), &Dependency::Runtime),
+                (&"C".to_string(), &Dependency::Build)
+            ]
+        );
+        assert_eq!(dag.outgoing_edges("C").count(), 0);
+        assert_eq!(dag.edges().count(), 2);
+
+        let mut weighted: Dag<String, String, u64> = Dag::default();
+        weighted.add_node(Node::new("A", "")).unwrap();
+        weighted.add_node(Node::new("B", "")).unwrap();
+        weighted.add_edge("A", "B").unwrap();
+        assert_eq!(weighted.get_edge("A", "B").map(EdgeWeight::weight), Some(0));
+        assert_eq!(().weight(), 1);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let mut dag = Dag::new();
+        dag.add_node(Node::new("A", "Node A")).unwrap();
+        dag.add_node(Node::new("B", "Node B")).unwrap();
+        dag.add_edge("A", "B").unwrap();
+
+        let json = serde_json::to_string(&dag).unwrap();
+        assert_eq!(
+            json,
+            r#"{"nodes":[{"id":"A","data":"Node A"},{"id":"B","data":"Node B"}],"edges":[["A","B",null]]}"#
+        );
+        let restored: Dag = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.node_count(), 2);
+        assert_eq!(restored.edge_count(), 1);
+
+        let cyclic = r#"{"nodes":[{"id":"A","data":""},{"id":"B","data":""}],"edges":[["A","B",null],["B","A",null]]}"#;
+        assert!(serde_json::from_str::<Dag>(cyclic).is_err());
+    }
 }
@@ -0,0 +1,116 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Write};
+
+use crate::Dag;
+
+/// Quote a string as a Graphviz DOT identifier or label
+pub(crate) fn dot_quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Quote a string as a Mermaid label
+pub(crate) fn mermaid_quote(value: &str) -> String {
+    let escaped = value
+        .replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+        .replace('\n', "<br/>");
+    format!("\"{}\"", escaped)
+}
+
+impl<K: Ord + Display, V: Display, E> Dag<K, V, E> {
+    /// Render the DAG in Graphviz DOT format, nodes and edges sorted by id
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph DAG {\n    rankdir=TB;\n    node [shape=box];\n");
+        for node in self.sorted_nodes() {
+            let label = format!("{}\n{}", node.id, node.data);
+            writeln!(
+                out,
+                "    {} [label={}];",
+                dot_quote(&node.id.to_string()),
+                dot_quote(&label)
+            )
+            .unwrap();
+        }
+        for (from, to, _) in self.sorted_edges() {
+            writeln!(
+                out,
+                "    {} -> {};",
+                dot_quote(&from.to_string()),
+                dot_quote(&to.to_string())
+            )
+            .unwrap();
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Render the DAG as a Mermaid flowchart, nodes and edges sorted by id
+    ///
+    /// Nodes get generated ids (`n0`, `n1`, ...) since Mermaid ids cannot hold
+    /// arbitrary characters; the node id is shown in the label.
+    pub fn to_mermaid(&self) -> String {
+        let nodes = self.sorted_nodes();
+        let mut out = String::from("graph TD\n");
+        for (i, node) in nodes.iter().enumerate() {
+            let label = format!("{}\n{}", node.id, node.data);
+            writeln!(out, "    n{}[{}]", i, mermaid_quote(&label)).unwrap();
+        }
+        let position: BTreeMap<&K, usize> =
+            nodes.iter().enumerate().map(|(i, n)| (&n.id, i)).collect();
+        for (from, to, _) in self.sorted_edges() {
+            writeln!(out, "    n{} --> n{}", position[from], position[to]).unwrap();
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Dag, Node};
+
+    fn diamond() -> Dag {
+        let mut dag = Dag::new();
+        for id in ["A", "B", "C", "D"] {
+            dag.add_node(Node::new(id, format!("Task {}", id))).unwrap();
+        }
+        dag.add_edge("A", "B").unwrap();
+        dag.add_edge("A", "C").unwrap();
+        dag.add_edge("B", "D").unwrap();
+        dag.add_edge("C", "D").unwrap();
+        dag
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let dot = diamond().to_dot();
+        assert!(dot.starts_with("digraph DAG {"));
+        assert!(dot.contains("    \"A\" [label=\"A\\nTask A\"];\n"));
+        assert!(dot.contains("    \"A\" -> \"B\";\n    \"A\" -> \"C\";\n"));
+        assert_eq!(dot, diamond().to_dot());
+
+        let mut dag = Dag::new();
+        dag.add_node(Node::new("say \"hi\"", "")).unwrap();
+        assert!(dag.to_dot().contains("\"say \\\"hi\\\"\""));
+    }
+
+    #[test]
+    fn test_to_mermaid() {
+        let mermaid = diamond().to_mermaid();
+        assert!(mermaid.starts_with("graph TD\n"));
+        assert!(mermaid.contains("    n0[\"A<br/>Task A\"]\n"));
+        assert!(mermaid.contains("    n1 --> n3\n"));
+        assert_eq!(mermaid.matches("-->").count(), 4);
+    }
+}
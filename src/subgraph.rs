@@ -0,0 +1,243 @@
+use std::borrow::Borrow;
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::hash::Hash;
+
+use crate::{Dag, DagNode};
+
+/// How `Dag::merge` handles a node id present in both DAGs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergePolicy {
+    Reject,       // Fail the merge
+    KeepExisting, // Keep this DAG's payload and edge data
+    Replace,      // Take the other DAG's payload and edge data
+}
+
+impl<K: Hash + Eq + Clone, V: Clone, E: Clone> Dag<K, V, E> {
+    /// Subgraph of the given nodes and all of their ancestors, e.g. everything needed to build them
+    pub fn subgraph_ancestors_of<Q>(&self, ids: &[&Q]) -> Result<Self, String>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + Display + ?Sized,
+    {
+        let mut keep = HashSet::new();
+        for id in ids {
+            let ancestors = self
+                .ancestors(*id)
+                .ok_or_else(|| format!("Node '{}' does not exist", id))?;
+            keep.extend(ancestors.into_iter().map(|node| &node.id));
+            keep.insert(self.nodes.get_key_value(*id).unwrap().0);
+        }
+        Ok(self.induced(&keep))
+    }
+
+    /// Subgraph of the given nodes and all of their descendants
+    pub fn subgraph_descendants_of<Q>(&self, ids: &[&Q]) -> Result<Self, String>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + Display + ?Sized,
+    {
+        let mut keep = HashSet::new();
+        for id in ids {
+            let descendants = self
+                .descendants(*id)
+                .ok_or_else(|| format!("Node '{}' does not exist", id))?;
+            keep.extend(descendants.into_iter().map(|node| &node.id));
+            keep.insert(self.nodes.get_key_value(*id).unwrap().0);
+        }
+        Ok(self.induced(&keep))
+    }
+
+    /// Subgraph of the nodes matching the predicate and the edges between them
+    pub fn induced_subgraph<F>(&self, mut keep: F) -> Self
+    where
+        F: FnMut(&DagNode<K, V>) -> bool,
+    {
+        let keep: HashSet<&K> = self
+            .nodes
+            .values()
+            .filter(|node| keep(node))
+            .map(|node| &node.id)
+            .collect();
+        self.induced(&keep)
+    }
+
+    /// Add the nodes and edges of another DAG
+    ///
+    /// Nodes in both DAGs are handled by `policy`. Nothing is changed if a node conflicts
+    /// under `MergePolicy::Reject` or the combined edges would create a cycle.
+    pub fn merge(&mut self, other: &Self, policy: MergePolicy) -> Result<(), String>
+    where
+        K: Display,
+    {
+        let mut merged = self.clone();
+        for node in other.nodes_by_sequence() {
+            let Some(existing) = merged.nodes.get_mut(&node.id) else {
+                merged.add_node(node.clone()).unwrap();
+                continue;
+            };
+            match policy {
+                MergePolicy::Reject => {
+                    return Err(format!(
+                        "Cannot merge: node '{}' exists in both DAGs",
+                        node.id
+                    ));
+                }
+                MergePolicy::KeepExisting => {}
+                MergePolicy::Replace => existing.data = node.data.clone(),
+            }
+        }
+
+        let mut edges: Vec<(&K, &K, &E)> = other.edges().collect();
+        edges.sort_by_key(|(from, to, _)| (other.sequence[*from], other.sequence[*to]));
+        let edges: Vec<(K, K, E)> = edges
+            .into_iter()
+            .filter(|(from, to, _)| {
+                policy != MergePolicy::KeepExisting || merged.get_edge(*from, *to).is_none()
+            })
+            .map(|(from, to, data)| (from.clone(), to.clone(), data.clone()))
+            .collect();
+        merged
+            .add_edges_batch(edges)
+            .map_err(|e| format!("Cannot merge: {}", e))?;
+
+        *self = merged;
+        Ok(())
+    }
+
+    /// New DAG with the given nodes, in insertion order, and the edges between them
+    fn induced(&self, keep: &HashSet<&K>) -> Self {
+        let mut dag = Dag::default();
+        for node in self.nodes_by_sequence() {
+            if keep.contains(&node.id) {
+                dag.insert_node(node.clone());
+            }
+        }
+        let edges = self
+            .edges()
+            .filter(|(from, to, _)| keep.contains(from) && keep.contains(to))
+            .map(|(from, to, data)| (from.clone(), to.clone(), data.clone()))
+            .collect();
+        // Edges between nodes of an acyclic DAG
+        dag.insert_edges_batch(edges)
+            .unwrap_or_else(|_| unreachable!("subgraph of a DAG has a cycle"));
+        dag
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Node;
+
+    fn dag(nodes: &[(&str, &str)], edges: &[(&str, &str)]) -> Dag {
+        let mut dag = Dag::new();
+        for (id, data) in nodes {
+            dag.add_node(Node::new(*id, *data)).unwrap();
+        }
+        for (from, to) in edges {
+            dag.add_edge(*from, *to).unwrap();
+        }
+        dag
+    }
+
+    fn ids(dag: &Dag) -> Vec<String> {
+        dag.topological_iter().map(|node| node.id.clone()).collect()
+    }
+
+    /// fetch -> compile -> link -> app, compile -> test, docs standalone
+    fn build() -> Dag {
+        dag(
+            &[
+                ("fetch", ""),
+                ("compile", ""),
+                ("link", ""),
+                ("test", ""),
+                ("app", ""),
+                ("docs", ""),
+            ],
+            &[
+                ("fetch", "compile"),
+                ("compile", "link"),
+                ("compile", "test"),
+                ("link", "app"),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_subgraph_ancestors_and_descendants() {
+        let dag = build();
+        let needed = dag.subgraph_ancestors_of(&["link"]).unwrap();
+        assert_eq!(ids(&needed), ["fetch", "compile", "link"]);
+        assert_eq!(needed.edge_count(), 2);
+
+        let needed = dag.subgraph_ancestors_of(&["app", "test", "docs"]).unwrap();
+        assert_eq!(needed.node_count(), 6);
+        assert_eq!(needed.edge_count(), 4);
+
+        let affected = dag.subgraph_descendants_of(&["compile"]).unwrap();
+        assert_eq!(ids(&affected), ["compile", "link", "test", "app"]);
+        assert_eq!(affected.edge_count(), 3);
+
+        assert_eq!(
+            dag.subgraph_descendants_of(&["compile", "missing"])
+                .unwrap_err(),
+            "Node 'missing' does not exist"
+        );
+    }
+
+    #[test]
+    fn test_induced_subgraph() {
+        let dag = build();
+        let sub = dag.induced_subgraph(|node| node.id != "compile");
+        assert_eq!(sub.node_count(), 5);
+        assert_eq!(sub.edge_count(), 1);
+        assert!(sub.get_edge("link", "app").is_some());
+        assert_eq!(dag.induced_subgraph(|_| false).node_count(), 0);
+    }
+
+    #[test]
+    fn test_merge() {
+        let base = dag(&[("A", "a"), ("B", "b")], &[("A", "B")]);
+        let other = dag(&[("B", "other b"), ("C", "c")], &[("B", "C")]);
+
+        let mut merged = base.clone();
+        assert_eq!(
+            merged.merge(&other, MergePolicy::Reject).unwrap_err(),
+            "Cannot merge: node 'B' exists in both DAGs"
+        );
+        assert_eq!(merged.node_count(), 2);
+
+        merged.merge(&other, MergePolicy::KeepExisting).unwrap();
+        assert_eq!(ids(&merged), ["A", "B", "C"]);
+        assert_eq!(merged.edge_count(), 2);
+        assert_eq!(merged.get_children("B").unwrap()[0].data, "c");
+        assert_eq!(merged.get_parents("C").unwrap()[0].data, "b");
+
+        let mut replaced = base.clone();
+        replaced.merge(&other, MergePolicy::Replace).unwrap();
+        assert_eq!(replaced.get_parents("C").unwrap()[0].data, "other b");
+
+        let disjoint = dag(&[("X", "")], &[]);
+        let mut union = base.clone();
+        union.merge(&disjoint, MergePolicy::Reject).unwrap();
+        assert_eq!(union.node_count(), 3);
+    }
+
+    #[test]
+    fn test_merge_rejects_cycles() {
+        let mut base = dag(&[("A", ""), ("B", "")], &[("A", "B")]);
+        let other = dag(
+            &[("B", ""), ("C", ""), ("A", "")],
+            &[("B", "C"), ("C", "A")],
+        );
+        assert_eq!(
+            base.merge(&other, MergePolicy::KeepExisting).unwrap_err(),
+            "Cannot merge: Adding the batch of 2 edges would create a cycle: B -> C -> A -> B"
+        );
+        assert_eq!(base.node_count(), 2);
+        assert_eq!(base.edge_count(), 1);
+        assert_eq!(ids(&base), ["A", "B"]);
+    }
+}
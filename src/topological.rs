@@ -0,0 +1,148 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap};
+use std::hash::Hash;
+
+use crate::{Dag, DagNode};
+
+/// Iterator over the nodes of a `Dag` in topological order, without cloning them
+///
+/// Among the nodes whose parents have all been yielded, the one with the lowest rank comes
+/// next, so the order only depends on the ranking, never on hash map iteration.
+pub struct TopologicalIter<'a, K, V, E> {
+    dag: &'a Dag<K, V, E>,
+    ranked: Vec<&'a K>,                // node ids by rank
+    rank: HashMap<&'a K, usize>,       // node_id -> rank
+    in_degree: HashMap<&'a K, usize>,  // node_id -> parents not yet yielded
+    ready: BinaryHeap<Reverse<usize>>, // ranks of nodes with no pending parent
+}
+
+impl<'a, K: Hash + Eq, V, E> TopologicalIter<'a, K, V, E> {
+    fn new(dag: &'a Dag<K, V, E>, ranked: Vec<&'a K>) -> Self {
+        let rank: HashMap<&K, usize> = ranked.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+        let in_degree: HashMap<&K, usize> = dag
+            .parents
+            .iter()
+            .map(|(id, parents)| (id, parents.len()))
+            .collect();
+        let ready = in_degree
+            .iter()
+            .filter(|(_, degree)| **degree == 0)
+            .map(|(id, _)| Reverse(rank[id]))
+            .collect();
+        Self {
+            dag,
+            ranked,
+            rank,
+            in_degree,
+            ready,
+        }
+    }
+}
+
+impl<'a, K: Hash + Eq, V, E> Iterator for TopologicalIter<'a, K, V, E> {
+    type Item = &'a DagNode<K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Reverse(rank) = self.ready.pop()?;
+        let id = self.ranked[rank];
+        for child in self.dag.edges[id].keys() {
+            let degree = self.in_degree.get_mut(child).unwrap();
+            *degree -= 1;
+            if *degree == 0 {
+                self.ready.push(Reverse(self.rank[child]));
+            }
+        }
+        Some(&self.dag.nodes[id])
+    }
+}
+
+impl<K: Hash + Eq + Clone, V, E> Dag<K, V, E> {
+    /// Iterate over the nodes in topological order, earliest added first among independent nodes
+    pub fn topological_iter(&self) -> TopologicalIter<'_, K, V, E> {
+        let mut ranked: Vec<&K> = self.nodes.keys().collect();
+        ranked.sort_by_key(|id| self.sequence[*id]);
+        TopologicalIter::new(self, ranked)
+    }
+
+    /// Topological order breaking ties between independent nodes with `compare`
+    ///
+    /// Nodes that compare equal keep insertion order. For a lexicographic order use
+    /// `topological_sort_by(|a, b| a.id.cmp(&b.id))`.
+    pub fn topological_sort_by<F>(&self, mut compare: F) -> Vec<&DagNode<K, V>>
+    where
+        F: FnMut(&DagNode<K, V>, &DagNode<K, V>) -> Ordering,
+    {
+        let mut ranked: Vec<&K> = self.nodes.keys().collect();
+        ranked.sort_by(|a, b| {
+            compare(&self.nodes[*a], &self.nodes[*b])
+                .then_with(|| self.sequence[*a].cmp(&self.sequence[*b]))
+        });
+        TopologicalIter::new(self, ranked).collect()
+    }
+
+    /// Topological order taking independent nodes by lowest `key`, then insertion order
+    pub fn topological_sort_by_key<T, F>(&self, mut key: F) -> Vec<&DagNode<K, V>>
+    where
+        T: Ord,
+        F: FnMut(&DagNode<K, V>) -> T,
+    {
+        let mut ranked: Vec<&K> = self.nodes.keys().collect();
+        ranked.sort_by_cached_key(|id| (key(&self.nodes[*id]), self.sequence[*id]));
+        TopologicalIter::new(self, ranked).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Dag, Node};
+
+    fn ids<'a>(nodes: impl IntoIterator<Item = &'a Node>) -> Vec<&'a str> {
+        nodes.into_iter().map(|node| node.id.as_str()).collect()
+    }
+
+    /// Two independent chains, C -> A and D -> B, added out of order
+    fn chains() -> Dag {
+        let mut dag = Dag::new();
+        for (id, priority) in [("D", "1"), ("C", "3"), ("B", "2"), ("A", "0")] {
+            dag.add_node(Node::new(id, priority)).unwrap();
+        }
+        dag.add_edge("C", "A").unwrap();
+        dag.add_edge("D", "B").unwrap();
+        dag
+    }
+
+    #[test]
+    fn test_insertion_order() {
+        let dag = chains();
+        assert_eq!(ids(dag.topological_iter()), ["D", "C", "B", "A"]);
+        let sorted = dag.topological_sort().unwrap();
+        assert_eq!(ids(&sorted), ["D", "C", "B", "A"]);
+
+        // Same order however the graph was built internally
+        for _ in 0..10 {
+            assert_eq!(ids(chains().topological_iter()), ["D", "C", "B", "A"]);
+        }
+    }
+
+    #[test]
+    fn test_custom_tie_breaking() {
+        let dag = chains();
+        assert_eq!(
+            ids(dag.topological_sort_by(|a, b| a.id.cmp(&b.id))),
+            ["C", "A", "D", "B"]
+        );
+        assert_eq!(
+            ids(dag.topological_sort_by(|a, b| b.id.cmp(&a.id))),
+            ["D", "C", "B", "A"]
+        );
+        assert_eq!(
+            ids(dag.topological_sort_by_key(|node| node.data.clone())),
+            ["D", "B", "C", "A"]
+        );
+        // Equal keys fall back to insertion order
+        assert_eq!(
+            ids(dag.topological_sort_by_key(|_| 0)),
+            ids(dag.topological_iter())
+        );
+    }
+}
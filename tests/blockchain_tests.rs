@@ -16,13 +16,92 @@ fn test_create_blockdag() {
 #[test]
 fn test_add_account() {
     let mut dag = BlockDAG::new(3);
-    dag.add_account("alice".to_string(), 1000);
+    dag.add_account("alice".to_string(), 1000).unwrap();
 
     let account = dag.get_account("alice").unwrap();
     assert_eq!(account.balance, 1000);
     assert_eq!(account.nonce, 0);
 }
 
+#[test]
+fn test_add_account_only_during_genesis_setup() {
+    for k in [0, 500] {
+        assert!(BlockDAG::new(k).params().validate().is_ok());
+    }
+    let mut dag = BlockDAG::new(3);
+    assert!(dag.params().validate().is_ok());
+    let empty_genesis = dag.genesis().commitment();
+    dag.add_account("alice".to_string(), 1000).unwrap();
+
+    // The account is part of the resealed genesis, which rebuilds the same state
+    assert_ne!(dag.genesis().commitment(), empty_genesis);
+    assert_eq!(dag.genesis().allocations["alice"], 1000);
+    let rebuilt = BlockDAG::with_params(dag.params().clone()).unwrap();
+    assert_eq!(rebuilt.genesis().commitment(), dag.genesis().commitment());
+    assert_eq!(rebuilt.state_commitment(), dag.state_commitment());
+    assert_eq!(
+        rebuilt.pruning_point_commitment(),
+        dag.pruning_point_commitment()
+    );
+
+    let b1 = Block::new("b1".to_string(), vec!["genesis".to_string()], vec![], 1);
+    dag.add_block(b1).unwrap();
+    assert!(dag.add_account("bob".to_string(), 500).is_err());
+    assert!(dag.get_account("bob").is_none());
+
+    // Test mode lifts the restriction
+    let mut dag = dag.with_test_mode(true);
+    dag.add_account("bob".to_string(), 500).unwrap();
+    assert_eq!(dag.get_account("bob").unwrap().balance, 500);
+}
+
+#[test]
+fn test_genesis_allocations() {
+    let genesis = GenesisConfig::new(1_000)
+        .with_allocation("alice", 1000)
+        .with_allocation("bob", 500);
+    let params = ConsensusParams::devnet().with_genesis(genesis.clone());
+    let mut dag = BlockDAG::with_params(params.clone()).unwrap();
+
+    assert_eq!(dag.get_account("alice").unwrap().balance, 1000);
+    assert_eq!(dag.get_account("bob").unwrap().balance, 500);
+    assert_eq!(dag.get_block(GENESIS_HASH).unwrap().timestamp, 1_000);
+    assert_eq!(dag.genesis().params_hash, params.hash());
+
+    // The same config always yields the same genesis and initial state
+    let other = BlockDAG::with_params(params).unwrap();
+    assert_eq!(dag.genesis().commitment(), other.genesis().commitment());
+    assert_eq!(dag.state_commitment(), other.state_commitment());
+
+    // Any change to the allocations or the rules changes the commitment
+    let changed = ConsensusParams::devnet().with_genesis(genesis.with_allocation("carol", 1));
+    assert_ne!(changed.genesis.commitment(), dag.genesis().commitment());
+    assert_ne!(
+        ConsensusParams::simnet()
+            .with_genesis(dag.genesis().clone())
+            .genesis
+            .commitment(),
+        dag.genesis().commitment()
+    );
+
+    let tx = Transaction::new(
+        "tx1".to_string(),
+        "alice".to_string(),
+        "bob".to_string(),
+        100,
+        0,
+    );
+    let b1 = Block::new(
+        "b1".to_string(),
+        vec![GENESIS_HASH.to_string()],
+        vec![tx],
+        1_001,
+    );
+    dag.add_block(b1).unwrap();
+    dag.execute_blue_chain().unwrap();
+    assert_eq!(dag.get_account("bob").unwrap().balance, 600);
+}
+
 #[test]
 fn test_single_block_chain() {
     let mut dag = BlockDAG::new(3);
@@ -42,8 +121,8 @@ fn test_transaction_execution_success() {
     let mut dag = BlockDAG::new(3);
 
     // set account
-    dag.add_account("alice".to_string(), 1000);
-    dag.add_account("bob".to_string(), 500);
+    dag.add_account("alice".to_string(), 1000).unwrap();
+    dag.add_account("bob".to_string(), 500).unwrap();
 
     // create transaction
     let tx = Transaction::new(
@@ -80,8 +159,8 @@ fn test_transaction_execution_insufficient_balance() {
     let mut dag = BlockDAG::new(3);
 
     // set account
-    dag.add_account("alice".to_string(), 50);
-    dag.add_account("bob".to_string(), 500);
+    dag.add_account("alice".to_string(), 50).unwrap();
+    dag.add_account("bob".to_string(), 500).unwrap();
 
     // create transaction (insufficient balance)
     let tx = Transaction::new(
@@ -118,8 +197,8 @@ fn test_transaction_execution_insufficient_balance() {
 fn test_transaction_execution_invalid_nonce() {
     let mut dag = BlockDAG::new(3);
 
-    dag.add_account("alice".to_string(), 1000);
-    dag.add_account("bob".to_string(), 500);
+    dag.add_account("alice".to_string(), 1000).unwrap();
+    dag.add_account("bob".to_string(), 500).unwrap();
 
     // create transaction (wrong nonce)
     let tx = Transaction::new(
@@ -153,8 +232,8 @@ fn test_transaction_revert() {
     let mut dag = BlockDAG::new(3);
 
     // set account
-    dag.add_account("alice".to_string(), 1000);
-    dag.add_account("bob".to_string(), 500);
+    dag.add_account("alice".to_string(), 1000).unwrap();
+    dag.add_account("bob".to_string(), 500).unwrap();
 
     // Create and execute transaction
     let tx = Transaction::new(
@@ -198,9 +277,9 @@ fn test_complex_dag_with_transactions() {
     let mut dag = BlockDAG::new(3);
 
     // set account
-    dag.add_account("alice".to_string(), 1000);
-    dag.add_account("bob".to_string(), 500);
-    dag.add_account("charlie".to_string(), 300);
+    dag.add_account("alice".to_string(), 1000).unwrap();
+    dag.add_account("bob".to_string(), 500).unwrap();
+    dag.add_account("charlie".to_string(), 300).unwrap();
 
     // Create blockchain with transactions
     let tx1 = Transaction::new(
@@ -267,8 +346,8 @@ fn test_multiple_transaction_revert_in_order() {
     let mut dag = BlockDAG::new(3);
 
     // set account
-    dag.add_account("alice".to_string(), 1000);
-    dag.add_account("bob".to_string(), 0);
+    dag.add_account("alice".to_string(), 1000).unwrap();
+    dag.add_account("bob".to_string(), 0).unwrap();
 
     // Create block with multiple transactions
     let tx1 = Transaction::new(
@@ -322,3 +401,1300 @@ fn test_multiple_transaction_revert_in_order() {
         assert_eq!(tx.status, TxStatus::Reverted);
     }
 }
+
+#[test]
+fn test_receipt_for_executed_transaction() {
+    let mut dag = BlockDAG::new(3);
+
+    dag.add_account("alice".to_string(), 1000).unwrap();
+    dag.add_account("bob".to_string(), 500).unwrap();
+
+    let tx = Transaction::new(
+        "tx1".to_string(),
+        "alice".to_string(),
+        "bob".to_string(),
+        100,
+        0,
+    )
+    .with_fee(5);
+
+    let block1 = Block::new(
+        "block1".to_string(),
+        vec!["genesis".to_string()],
+        vec![tx],
+        1,
+    );
+
+    dag.add_block(block1).unwrap();
+    dag.execute_blue_chain().unwrap();
+
+    // Fee is paid by the sender on top of the amount
+    assert_eq!(dag.get_account("alice").unwrap().balance, 895);
+    assert_eq!(dag.get_account("bob").unwrap().balance, 600);
+
+    let receipt = dag.receipt("tx1").unwrap();
+    assert!(receipt.is_accepted());
+    assert_eq!(receipt.block_hash, "block1");
+    assert_eq!(receipt.accepting_block, "block1");
+    assert_eq!(receipt.fee, 5);
+    assert_eq!(receipt.failure_reason(), None);
+    assert_eq!(receipt.balance_deltas["alice"], -105);
+    assert_eq!(receipt.balance_deltas["bob"], 100);
+
+    assert_eq!(dag.block_receipts("block1").len(), 1);
+    assert!(dag.receipt_in("block1", "tx1").is_some());
+}
+
+#[test]
+fn test_receipt_records_failure_reason() {
+    let mut dag = BlockDAG::new(3);
+
+    dag.add_account("alice".to_string(), 50).unwrap();
+
+    let tx = Transaction::new(
+        "tx1".to_string(),
+        "alice".to_string(),
+        "bob".to_string(),
+        100,
+        0,
+    );
+    let block1 = Block::new(
+        "block1".to_string(),
+        vec!["genesis".to_string()],
+        vec![tx],
+        1,
+    );
+
+    dag.add_block(block1).unwrap();
+    dag.execute_blue_chain().unwrap();
+
+    let receipt = dag.receipt("tx1").unwrap();
+    assert!(!receipt.is_accepted());
+    assert!(
+        receipt
+            .failure_reason()
+            .unwrap()
+            .contains("Insufficient balance")
+    );
+    assert_eq!(receipt.fee, 0);
+    assert!(receipt.balance_deltas.is_empty());
+
+    // Unknown transactions have no receipt
+    assert!(dag.receipt("tx2").is_none());
+}
+
+#[test]
+fn test_receipt_marked_reverted() {
+    let mut dag = BlockDAG::new(3);
+
+    dag.add_account("alice".to_string(), 1000).unwrap();
+
+    let tx = Transaction::new(
+        "tx1".to_string(),
+        "alice".to_string(),
+        "bob".to_string(),
+        100,
+        0,
+    )
+    .with_fee(10);
+    let block1 = Block::new(
+        "block1".to_string(),
+        vec!["genesis".to_string()],
+        vec![tx],
+        1,
+    );
+
+    dag.add_block(block1).unwrap();
+    dag.execute_blue_chain().unwrap();
+    dag.revert_block("block1").unwrap();
+
+    // Reverting restores the burned fee as well
+    assert_eq!(dag.get_account("alice").unwrap().balance, 1000);
+    assert_eq!(dag.receipt("tx1").unwrap().status, TxStatus::Reverted);
+}
+
+#[test]
+fn test_red_block_transactions_accepted_by_merging_chain_block() {
+    // k=0 forces a single blue chain:
+    //      genesis
+    //       /   \
+    //      a1   b1 (red)
+    //       \   /
+    //         c
+    let mut dag = BlockDAG::new(0);
+
+    dag.add_account("alice".to_string(), 1000).unwrap();
+
+    let tx_red = Transaction::new(
+        "tx_red".to_string(),
+        "alice".to_string(),
+        "bob".to_string(),
+        100,
+        0,
+    );
+    let tx_chain = Transaction::new(
+        "tx_chain".to_string(),
+        "alice".to_string(),
+        "bob".to_string(),
+        50,
+        1,
+    );
+
+    let a1 = Block::new("a1".to_string(), vec!["genesis".to_string()], vec![], 100);
+    let b1 = Block::new(
+        "b1".to_string(),
+        vec!["genesis".to_string()],
+        vec![tx_red],
+        200,
+    );
+    let c = Block::new(
+        "c".to_string(),
+        vec!["a1".to_string(), "b1".to_string()],
+        vec![tx_chain],
+        300,
+    );
+    dag.add_block(a1).unwrap();
+    dag.add_block(b1).unwrap();
+    dag.add_block(c).unwrap();
+
+    assert_eq!(dag.get_block("b1").unwrap().color, BlockColor::Red);
+    assert_eq!(dag.get_block("c").unwrap().color, BlockColor::Blue);
+
+    dag.execute_blue_chain().unwrap();
+
+    // The red block's transaction is accepted by the chain block merging it
+    assert_eq!(dag.get_account("bob").unwrap().balance, 150);
+    assert_eq!(dag.accepting_block("tx_red"), Some("c"));
+    assert_eq!(dag.accepting_block("tx_chain"), Some("c"));
+    assert_eq!(dag.receipt("tx_red").unwrap().block_hash, "b1");
+
+    let acceptance = dag.acceptance_data("c").unwrap();
+    assert_eq!(acceptance.len(), 2);
+    assert_eq!(acceptance[0].block_hash, "b1");
+    assert_eq!(acceptance[0].accepted_tx_ids, vec!["tx_red".to_string()]);
+    assert_eq!(acceptance[1].block_hash, "c");
+    assert_eq!(acceptance[1].accepted_tx_ids, vec!["tx_chain".to_string()]);
+
+    // a1 merges nothing but itself
+    assert_eq!(dag.acceptance_data("a1").unwrap().len(), 1);
+}
+
+#[test]
+fn test_blue_side_block_accepted_by_selected_chain() {
+    //      genesis
+    //       /   \
+    //      b     c (blue, off the selected chain)
+    //       \   /
+    //         d
+    let mut dag = BlockDAG::new(3);
+    dag.add_account("alice".to_string(), 1000).unwrap();
+
+    let t1 = Transaction::new(
+        "t1".to_string(),
+        "alice".to_string(),
+        "bob".to_string(),
+        100,
+        0,
+    );
+    let t2 = Transaction::new(
+        "t2".to_string(),
+        "alice".to_string(),
+        "bob".to_string(),
+        50,
+        1,
+    );
+    dag.add_block(Block::new(
+        "b".to_string(),
+        vec!["genesis".to_string()],
+        vec![t1],
+        2,
+    ))
+    .unwrap();
+    dag.add_block(Block::new(
+        "c".to_string(),
+        vec!["genesis".to_string()],
+        vec![t2],
+        1,
+    ))
+    .unwrap();
+    dag.add_block(Block::new(
+        "d".to_string(),
+        vec!["b".to_string(), "c".to_string()],
+        vec![],
+        3,
+    ))
+    .unwrap();
+
+    assert_eq!(dag.get_block("c").unwrap().color, BlockColor::Blue);
+    assert_eq!(dag.selected_parent("d"), Some("b"));
+    assert_eq!(dag.selected_chain(), ["genesis", "b", "d"]);
+
+    dag.execute_blue_chain().unwrap();
+
+    assert_eq!(dag.get_account("bob").unwrap().balance, 150);
+    assert_eq!(dag.accepting_block("t1"), Some("b"));
+    assert_eq!(dag.accepting_block("t2"), Some("d"));
+    assert!(dag.acceptance_data("c").is_none());
+
+    let acceptance = dag.acceptance_data("d").unwrap();
+    assert_eq!(acceptance.len(), 2);
+    assert_eq!(acceptance[0].block_hash, "c");
+    assert_eq!(acceptance[0].accepted_tx_ids, vec!["t2".to_string()]);
+    assert_eq!(acceptance[1].block_hash, "d");
+}
+
+#[test]
+fn test_confirmations_grow_with_blue_score() {
+    let mut dag = BlockDAG::new(3);
+
+    dag.add_account("alice".to_string(), 1000).unwrap();
+
+    let tx = Transaction::new(
+        "tx1".to_string(),
+        "alice".to_string(),
+        "bob".to_string(),
+        100,
+        0,
+    );
+    let b1 = Block::new("b1".to_string(), vec!["genesis".to_string()], vec![tx], 1);
+    dag.add_block(b1).unwrap();
+    dag.execute_blue_chain().unwrap();
+
+    assert_eq!(dag.confirmations("tx1"), Some(1));
+
+    let b2 = Block::new("b2".to_string(), vec!["b1".to_string()], vec![], 2);
+    let b3 = Block::new("b3".to_string(), vec!["b2".to_string()], vec![], 3);
+    dag.add_block(b2).unwrap();
+    dag.add_block(b3).unwrap();
+
+    assert_eq!(dag.confirmations("tx1"), Some(3));
+
+    // Failed or unknown transactions have no confirmations
+    assert_eq!(dag.confirmations("missing"), None);
+}
+
+#[test]
+fn test_tx_index_by_id_block_and_address() {
+    let mut dag = BlockDAG::new(3);
+    dag.enable_tx_index();
+
+    dag.add_account("alice".to_string(), 1000).unwrap();
+
+    let tx1 = Transaction::new(
+        "tx1".to_string(),
+        "alice".to_string(),
+        "bob".to_string(),
+        100,
+        0,
+    );
+    let tx2 = Transaction::new(
+        "tx2".to_string(),
+        "bob".to_string(),
+        "charlie".to_string(),
+        40,
+        0,
+    );
+    let tx3 = Transaction::new(
+        "tx3".to_string(),
+        "alice".to_string(),
+        "charlie".to_string(),
+        5000,
+        1,
+    );
+
+    let b1 = Block::new("b1".to_string(), vec!["genesis".to_string()], vec![tx1], 1);
+    let b2 = Block::new("b2".to_string(), vec!["b1".to_string()], vec![tx2, tx3], 2);
+    dag.add_block(b1).unwrap();
+    dag.add_block(b2).unwrap();
+
+    let index = dag.tx_index().unwrap();
+    assert_eq!(index.blocks_containing("tx2"), ["b2".to_string()]);
+    assert_eq!(
+        index.block_transactions("b2"),
+        ["tx2".to_string(), "tx3".to_string()]
+    );
+
+    // Bob received tx1 and sent tx2
+    let history = index.address_history("bob", 0, 10);
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].tx_id, "tx1");
+    assert_eq!(history[0].direction, TxDirection::Incoming);
+    assert_eq!(history[0].counterparty, "alice");
+    assert_eq!(history[1].tx_id, "tx2");
+    assert_eq!(history[1].direction, TxDirection::Outgoing);
+    assert_eq!(history[1].amount, 40);
+
+    // Not accepted before the chain is executed
+    assert!(history.iter().all(|e| e.accepting_block.is_none()));
+
+    dag.execute_blue_chain().unwrap();
+
+    let index = dag.tx_index().unwrap();
+    let alice = index.address_history("alice", 0, 10);
+    assert_eq!(alice[0].accepting_block.as_deref(), Some("b1"));
+    // tx3 failed, so it stays unaccepted
+    assert_eq!(alice[1].tx_id, "tx3");
+    assert_eq!(alice[1].accepting_block, None);
+}
+
+#[test]
+fn test_tx_index_one_entry_per_address_and_transaction() {
+    let mut dag = BlockDAG::new(3);
+    dag.enable_tx_index();
+    dag.add_account("alice".to_string(), 1000).unwrap();
+
+    let self_transfer = Transaction::new(
+        "self".to_string(),
+        "alice".to_string(),
+        "alice".to_string(),
+        10,
+        0,
+    );
+    let pay = Transaction::new(
+        "pay".to_string(),
+        "alice".to_string(),
+        "bob".to_string(),
+        100,
+        1,
+    );
+    // The same transaction included by two parallel blocks
+    let b1 = Block::new(
+        "b1".to_string(),
+        vec!["genesis".to_string()],
+        vec![self_transfer, pay.clone()],
+        1,
+    );
+    let b2 = Block::new("b2".to_string(), vec!["genesis".to_string()], vec![pay], 2);
+    dag.add_block(b1).unwrap();
+    dag.add_block(b2).unwrap();
+
+    let index = dag.tx_index().unwrap();
+    assert_eq!(
+        index.blocks_containing("pay"),
+        ["b1".to_string(), "b2".to_string()]
+    );
+    let alice = index.address_history("alice", 0, 10);
+    assert_eq!(alice.len(), 2);
+    assert_eq!(alice[0].tx_id, "self");
+    assert_eq!(alice[0].direction, TxDirection::Outgoing);
+    assert_eq!(alice[1].tx_id, "pay");
+    assert_eq!(index.address_tx_count("bob"), 1);
+
+    // The copy in chain block b2 fails on its nonce, then c merges b1 and accepts
+    // the copy in b1
+    let c = Block::new(
+        "c".to_string(),
+        vec!["b1".to_string(), "b2".to_string()],
+        vec![],
+        3,
+    );
+    dag.add_block(c).unwrap();
+    dag.execute_blue_chain().unwrap();
+    assert_eq!(dag.receipt_in("c", "pay").unwrap().block_hash, "b1");
+    let bob = dag.tx_index().unwrap().address_history("bob", 0, 10);
+    assert_eq!(bob.len(), 1);
+    assert_eq!(bob[0].block_hash, "b1");
+    assert_eq!(bob[0].accepting_block.as_deref(), Some("c"));
+}
+
+#[test]
+fn test_rejected_duplicate_keeps_accepted_receipt() {
+    let mut dag = BlockDAG::new(3);
+    dag.add_account("alice".to_string(), 1000).unwrap();
+
+    // Chain block b2 accepts "pay" first, then c merges b1 and its copy fails
+    let pay = Transaction::new(
+        "pay".to_string(),
+        "alice".to_string(),
+        "bob".to_string(),
+        100,
+        0,
+    );
+    for (hash, timestamp) in [("b1", 1), ("b2", 2)] {
+        let block = Block::new(
+            hash.to_string(),
+            vec!["genesis".to_string()],
+            vec![pay.clone()],
+            timestamp,
+        );
+        dag.add_block(block).unwrap();
+    }
+    let c = Block::new(
+        "c".to_string(),
+        vec!["b1".to_string(), "b2".to_string()],
+        vec![],
+        3,
+    );
+    dag.add_block(c).unwrap();
+    assert_eq!(dag.selected_chain(), ["genesis", "b2", "c"]);
+
+    dag.execute_blue_chain().unwrap();
+    assert_eq!(dag.get_account("bob").unwrap().balance, 100);
+    assert!(!dag.receipt_in("c", "pay").unwrap().is_accepted());
+    assert!(dag.receipt("pay").unwrap().is_accepted());
+    assert_eq!(dag.accepting_block("pay"), Some("b2"));
+    assert_eq!(dag.confirmations("pay"), Some(2));
+}
+
+#[test]
+fn test_tx_index_pagination_and_late_enable() {
+    let mut dag = BlockDAG::new(3);
+
+    dag.add_account("alice".to_string(), 1000).unwrap();
+
+    let mut parent = "genesis".to_string();
+    for i in 0..5 {
+        let tx = Transaction::new(
+            format!("tx{}", i),
+            "alice".to_string(),
+            "bob".to_string(),
+            10,
+            i,
+        );
+        let hash = format!("b{}", i);
+        let block = Block::new(hash.clone(), vec![parent], vec![tx], i + 1);
+        dag.add_block(block).unwrap();
+        parent = hash;
+    }
+
+    // Enabling the index later picks up existing blocks
+    dag.enable_tx_index();
+    let index = dag.tx_index().unwrap();
+
+    assert_eq!(index.address_tx_count("alice"), 5);
+    let page: Vec<&str> = index
+        .address_history("alice", 2, 2)
+        .iter()
+        .map(|e| e.tx_id.as_str())
+        .collect();
+    assert_eq!(page, vec!["tx2", "tx3"]);
+    assert!(index.address_history("alice", 5, 2).is_empty());
+    assert!(index.address_history("nobody", 0, 2).is_empty());
+}
+
+#[test]
+fn test_finality_rejects_deep_reorg() {
+    // k=0 forces a single blue chain, finality depth of 2 blue blocks
+    let mut dag = BlockDAG::new(0)
+        .with_merge_depth(2)
+        .unwrap()
+        .with_finality_depth(2)
+        .unwrap();
+
+    // genesis -> a1 -> a2 -> a3
+    let a1 = Block::new("a1".to_string(), vec!["genesis".to_string()], vec![], 100);
+    let a2 = Block::new("a2".to_string(), vec!["a1".to_string()], vec![], 110);
+    let a3 = Block::new("a3".to_string(), vec!["a2".to_string()], vec![], 120);
+    dag.add_block(a1).unwrap();
+    dag.add_block(a2).unwrap();
+    dag.add_block(a3).unwrap();
+
+    assert_eq!(dag.virtual_blue_score(), 4);
+    assert_eq!(dag.finality_point().unwrap().hash, "a1");
+
+    // A competing branch from genesis with earlier timestamps
+    let b1 = Block::new("b1".to_string(), vec!["genesis".to_string()], vec![], 50);
+    let b2 = Block::new("b2".to_string(), vec!["b1".to_string()], vec![], 60);
+    dag.add_block(b1).unwrap();
+    dag.add_block(b2).unwrap();
+    assert_eq!(dag.get_block("b1").unwrap().color, BlockColor::Red);
+
+    // b3 would make the b-branch win and turn the finalized a1 red
+    let b3 = Block::new("b3".to_string(), vec!["b2".to_string()], vec![], 70);
+    let err = dag.add_block(b3).unwrap_err();
+    assert_eq!(
+        err,
+        RuleError::FinalityViolation {
+            finalized_block: "a1".to_string(),
+            finality_depth: 2,
+        }
+    );
+
+    // The DAG is left untouched
+    assert!(dag.get_block("b3").is_none());
+    assert_eq!(dag.get_block("a1").unwrap().color, BlockColor::Blue);
+    assert_eq!(dag.get_block("a3").unwrap().weight, 4);
+}
+
+#[test]
+fn test_prune_below_finalized_block() {
+    let mut dag = BlockDAG::new(3)
+        .with_merge_depth(2)
+        .unwrap()
+        .with_finality_depth(2)
+        .unwrap();
+
+    dag.add_account("alice".to_string(), 1000).unwrap();
+
+    let mut parent = "genesis".to_string();
+    for i in 1..=5 {
+        let hash = format!("b{}", i);
+        let block = Block::new(hash.clone(), vec![parent], vec![], i);
+        dag.add_block(block).unwrap();
+        parent = hash;
+    }
+    assert_eq!(dag.get_block("b5").unwrap().weight, 6);
+
+    // b4 is within the finality depth, so it cannot become the pruning point
+    assert!(dag.prune("b4").is_err());
+
+    dag.prune("b2").unwrap();
+    assert_eq!(dag.pruning_point(), "b2");
+    assert_eq!(dag.pruning_point_commitment(), dag.state_commitment());
+
+    // Bodies below the pruning point are gone, headers are kept
+    assert!(dag.get_block("genesis").is_none());
+    assert!(dag.get_block("b1").is_none());
+    assert_eq!(dag.get_header("b1").unwrap().parent_hashes, ["genesis"]);
+    assert_eq!(dag.get_all_blocks().len(), 4);
+
+    // Weights keep counting blue blocks from genesis
+    assert_eq!(dag.get_block("b2").unwrap().weight, 3);
+    assert_eq!(dag.get_block("b5").unwrap().weight, 6);
+
+    // New blocks cannot reference pruned blocks
+    let stale = Block::new("stale".to_string(), vec!["b1".to_string()], vec![], 10);
+    assert!(dag.add_block(stale).is_err());
+
+    let b6 = Block::new("b6".to_string(), vec!["b5".to_string()], vec![], 6);
+    dag.add_block(b6).unwrap();
+    assert_eq!(dag.get_block("b6").unwrap().weight, 7);
+}
+
+#[test]
+fn test_duplicate_block_rejected() {
+    let mut dag = BlockDAG::new(3)
+        .with_merge_depth(1)
+        .unwrap()
+        .with_finality_depth(1)
+        .unwrap();
+    let b1 = Block::new("b1".to_string(), vec!["genesis".to_string()], vec![], 1);
+    let b2 = Block::new("b2".to_string(), vec!["b1".to_string()], vec![], 2);
+    let b3 = Block::new("b3".to_string(), vec!["b2".to_string()], vec![], 3);
+    dag.add_block(b1.clone()).unwrap();
+    dag.add_block(b2).unwrap();
+
+    // Resubmitting a block leaves the original and its children in place
+    let resubmitted = Block::new("b1".to_string(), vec!["genesis".to_string()], vec![], 9);
+    assert_eq!(
+        dag.add_block(resubmitted).unwrap_err(),
+        RuleError::DuplicateBlock("b1".to_string())
+    );
+    assert_eq!(dag.get_block("b1").unwrap().timestamp, 1);
+    dag.add_block(b3).unwrap();
+    assert_eq!(dag.selected_chain(), ["genesis", "b1", "b2", "b3"]);
+
+    // Pruned blocks cannot come back either
+    dag.prune("b2").unwrap();
+    assert_eq!(
+        dag.add_block(b1).unwrap_err(),
+        RuleError::DuplicateBlock("b1".to_string())
+    );
+}
+
+#[test]
+fn test_execute_after_prune() {
+    let mut dag = BlockDAG::new(3)
+        .with_merge_depth(1)
+        .unwrap()
+        .with_finality_depth(1)
+        .unwrap();
+    dag.add_account("alice".to_string(), 1000).unwrap();
+
+    let mut parent = "genesis".to_string();
+    for i in 1..=4 {
+        let hash = format!("b{}", i);
+        let tx = Transaction::new(
+            format!("tx{}", i),
+            "alice".to_string(),
+            "bob".to_string(),
+            10,
+            i - 1,
+        );
+        dag.add_block(Block::new(hash.clone(), vec![parent], vec![tx], i))
+            .unwrap();
+        parent = hash;
+    }
+    dag.execute_blue_chain().unwrap();
+    let executed = dag.state_commitment();
+    assert_eq!(dag.get_account("bob").unwrap().balance, 40);
+
+    // The pruning point state includes the transactions up to b2
+    dag.prune("b2").unwrap();
+    assert_ne!(dag.pruning_point_commitment(), executed);
+
+    // Re-executing the retained blocks starts from that state
+    dag.execute_blue_chain().unwrap();
+    assert_eq!(dag.state_commitment(), executed);
+    assert_eq!(dag.get_account("bob").unwrap().balance, 40);
+    assert_eq!(dag.get_account("alice").unwrap().nonce, 4);
+    assert!(dag.receipt("tx4").unwrap().is_accepted());
+
+    // The pruning point's own transaction is not executed a second time
+    let tx2 = dag.receipt("tx2").unwrap();
+    assert!(tx2.is_accepted());
+    assert_eq!(tx2.accepting_block, "b2");
+    assert_eq!(
+        dag.get_block("b2").unwrap().transactions[0].status,
+        TxStatus::Executed
+    );
+
+    // The pruning point state survives a save and reload
+    let mut store = MemoryStore::new();
+    dag.save(&mut store).unwrap();
+    let mut reopened = BlockDAG::load(dag.params().clone(), &store).unwrap();
+    assert_eq!(
+        reopened.pruning_point_commitment(),
+        dag.pruning_point_commitment()
+    );
+    reopened.execute_blue_chain().unwrap();
+    assert_eq!(reopened.state_commitment(), executed);
+}
+
+#[test]
+fn test_merge_depth_rejects_deep_side_branch() {
+    // k=0 forces a single blue chain, merge depth of 2 blue blocks:
+    //   genesis -> a1 -> a2 -> a3 -> a4
+    //      \                          \
+    //       s1 (red) ----------------- m
+    let mut dag = BlockDAG::new(0).with_merge_depth(2).unwrap();
+
+    let mut parent = "genesis".to_string();
+    for i in 1..=4 {
+        let hash = format!("a{}", i);
+        let block = Block::new(hash.clone(), vec![parent], vec![], 100 + i);
+        dag.add_block(block).unwrap();
+        parent = hash;
+    }
+    let s1 = Block::new("s1".to_string(), vec!["genesis".to_string()], vec![], 500);
+    dag.add_block(s1).unwrap();
+    assert_eq!(dag.get_block("s1").unwrap().color, BlockColor::Red);
+
+    let m = Block::new(
+        "m".to_string(),
+        vec!["a4".to_string(), "s1".to_string()],
+        vec![],
+        600,
+    );
+    let err = dag.add_block(m).unwrap_err();
+    assert_eq!(
+        err,
+        RuleError::MergeDepthViolation {
+            block: "m".to_string(),
+            merged_block: "s1".to_string(),
+            merge_depth_root: "a2".to_string(),
+        }
+    );
+    assert!(dag.get_block("m").is_none());
+}
+
+#[test]
+fn test_merge_depth_allows_recent_side_branch() {
+    let mut dag = BlockDAG::new(0).with_merge_depth(2).unwrap();
+
+    let mut parent = "genesis".to_string();
+    for i in 1..=4 {
+        let hash = format!("a{}", i);
+        let block = Block::new(hash.clone(), vec![parent], vec![], 100 + i);
+        dag.add_block(block).unwrap();
+        parent = hash;
+    }
+
+    // s1 forks off the merge depth root, so it is in its future
+    let s1 = Block::new("s1".to_string(), vec!["a2".to_string()], vec![], 500);
+    dag.add_block(s1).unwrap();
+
+    let m = Block::new(
+        "m".to_string(),
+        vec!["a4".to_string(), "s1".to_string()],
+        vec![],
+        600,
+    );
+    dag.add_block(m).unwrap();
+
+    assert_eq!(dag.selected_parent("m"), Some("a4"));
+    assert!(dag.mergeset("m").contains("s1"));
+    assert_eq!(dag.mergeset("m").len(), 1);
+}
+
+/// genesis -> a1 -> a2 -> a3 with a side block r off genesis, under k=1 and a merge
+/// depth of 2 blue blocks
+fn deep_red_side_block() -> BlockDAG {
+    let mut dag = BlockDAG::new(1).with_merge_depth(2).unwrap();
+    let mut parent = "genesis".to_string();
+    for i in 1..=3 {
+        let hash = format!("a{}", i);
+        let block = Block::new(hash.clone(), vec![parent], vec![], 100 + i);
+        dag.add_block(block).unwrap();
+        parent = hash;
+    }
+    let r = Block::new("r".to_string(), vec!["genesis".to_string()], vec![], 500);
+    dag.add_block(r).unwrap();
+    dag
+}
+
+#[test]
+fn test_merge_depth_allows_kosherized_red_block() {
+    let mut dag = deep_red_side_block();
+
+    // b merges r while it is shallow and stays blue, in the future of the merge
+    // depth root a2
+    let b = Block::new(
+        "b".to_string(),
+        vec!["a2".to_string(), "r".to_string()],
+        vec![],
+        50,
+    );
+    dag.add_block(b).unwrap();
+    assert_eq!(dag.get_block("r").unwrap().color, BlockColor::Red);
+    assert_eq!(dag.get_block("b").unwrap().color, BlockColor::Blue);
+
+    let m = Block::new(
+        "m".to_string(),
+        vec!["a3".to_string(), "b".to_string()],
+        vec![],
+        600,
+    );
+    dag.add_block(m).unwrap();
+    assert_eq!(dag.selected_parent("m"), Some("a3"));
+    assert!(dag.mergeset("m").contains("r"));
+}
+
+#[test]
+fn test_merge_depth_rejects_red_block_kosherized_below_root() {
+    let mut dag = deep_red_side_block();
+
+    // b merges r and is blue, but is not in the future of the merge depth root a2
+    let b = Block::new(
+        "b".to_string(),
+        vec!["a1".to_string(), "r".to_string()],
+        vec![],
+        50,
+    );
+    dag.add_block(b).unwrap();
+    assert_eq!(dag.get_block("r").unwrap().color, BlockColor::Red);
+    assert_eq!(dag.get_block("b").unwrap().color, BlockColor::Blue);
+
+    let m = Block::new(
+        "m".to_string(),
+        vec!["a3".to_string(), "b".to_string()],
+        vec![],
+        600,
+    );
+    assert_eq!(
+        dag.add_block(m).unwrap_err(),
+        RuleError::MergeDepthViolation {
+            block: "m".to_string(),
+            merged_block: "r".to_string(),
+            merge_depth_root: "a2".to_string(),
+        }
+    );
+}
+
+#[test]
+fn test_max_block_parents_enforced() {
+    let mut dag = BlockDAG::new(3).with_max_block_parents(2).unwrap();
+
+    for i in 1..=3 {
+        let block = Block::new(format!("b{}", i), vec!["genesis".to_string()], vec![], i);
+        dag.add_block(block).unwrap();
+    }
+
+    let merge = Block::new(
+        "m".to_string(),
+        vec!["b1".to_string(), "b2".to_string(), "b3".to_string()],
+        vec![],
+        10,
+    );
+    assert_eq!(
+        dag.add_block(merge).unwrap_err(),
+        RuleError::TooManyParents { count: 3, max: 2 }
+    );
+    assert!(dag.get_block("m").is_none());
+
+    // The limit applies to the parents left after normalization
+    let mut dag = dag.with_parent_normalization(true);
+    let c1 = Block::new("c1".to_string(), vec!["b1".to_string()], vec![], 4);
+    dag.add_block(c1).unwrap();
+    let merge = Block::new(
+        "m".to_string(),
+        vec!["b1".to_string(), "b2".to_string(), "c1".to_string()],
+        vec![],
+        10,
+    );
+    dag.add_block(merge).unwrap();
+    assert_eq!(dag.get_block("m").unwrap().parent_hashes, ["b2", "c1"]);
+
+    // Far too many parents are rejected before normalization walks their past
+    let parents: Vec<String> = (0..9).map(|i| format!("p{}", i)).collect();
+    let flood = Block::new("flood".to_string(), parents, vec![], 11);
+    assert_eq!(
+        dag.add_block(flood).unwrap_err(),
+        RuleError::TooManyParents { count: 9, max: 2 }
+    );
+}
+
+#[test]
+fn test_duplicate_parents_are_deduplicated() {
+    let mut dag = BlockDAG::new(3).with_max_block_parents(1).unwrap();
+
+    // Duplicates do not count against the parent limit
+    let b1 = Block::new(
+        "b1".to_string(),
+        vec!["genesis".to_string(), "genesis".to_string()],
+        vec![],
+        1,
+    );
+    dag.add_block(b1).unwrap();
+
+    let block = dag.get_block("b1").unwrap();
+    assert_eq!(block.parent_hashes, ["genesis"]);
+    assert_eq!(block.color, BlockColor::Blue);
+    assert_eq!(block.weight, 2);
+
+    // In-degree counting still lets children be ordered
+    let b2 = Block::new("b2".to_string(), vec!["b1".to_string()], vec![], 2);
+    dag.add_block(b2).unwrap();
+    assert_eq!(dag.get_block("b2").unwrap().weight, 3);
+}
+
+#[test]
+fn test_mergeset_size_limit_enforced() {
+    let mut dag = BlockDAG::new(1).with_mergeset_size_limit(2).unwrap();
+
+    for i in 1..=4 {
+        let block = Block::new(format!("b{}", i), vec!["genesis".to_string()], vec![], i);
+        dag.add_block(block).unwrap();
+    }
+
+    // Merging three side blocks exceeds the limit
+    let merge = Block::new(
+        "m".to_string(),
+        vec![
+            "b1".to_string(),
+            "b2".to_string(),
+            "b3".to_string(),
+            "b4".to_string(),
+        ],
+        vec![],
+        10,
+    );
+    assert_eq!(
+        dag.add_block(merge).unwrap_err(),
+        RuleError::MergesetTooLarge { size: 3, limit: 2 }
+    );
+
+    let missing = Block::new("x".to_string(), vec!["nope".to_string()], vec![], 11);
+    assert_eq!(
+        dag.add_block(missing).unwrap_err(),
+        RuleError::MissingParent("nope".to_string())
+    );
+}
+
+#[test]
+fn test_redundant_parent_rejected() {
+    // b2 lists both b1 and its ancestor genesis as parents
+    let mut dag = BlockDAG::new(3);
+
+    let b1 = Block::new("b1".to_string(), vec!["genesis".to_string()], vec![], 1);
+    dag.add_block(b1).unwrap();
+
+    let b2 = Block::new(
+        "b2".to_string(),
+        vec!["genesis".to_string(), "b1".to_string()],
+        vec![],
+        2,
+    );
+    assert_eq!(
+        dag.add_block(b2).unwrap_err(),
+        RuleError::RedundantParent {
+            parent: "genesis".to_string(),
+            descendant_parent: "b1".to_string(),
+        }
+    );
+    assert!(dag.get_block("b2").is_none());
+}
+
+#[test]
+fn test_parent_normalization() {
+    let mut dag = BlockDAG::new(3).with_parent_normalization(true);
+
+    let b1 = Block::new("b1".to_string(), vec!["genesis".to_string()], vec![], 1);
+    let b2 = Block::new("b2".to_string(), vec!["genesis".to_string()], vec![], 2);
+    let b3 = Block::new("b3".to_string(), vec!["b1".to_string()], vec![], 3);
+    dag.add_block(b1).unwrap();
+    dag.add_block(b2).unwrap();
+    dag.add_block(b3).unwrap();
+
+    // Template building: drop ancestors, duplicates and unknown blocks
+    let parents = dag.normalize_parents(&[
+        "b1".to_string(),
+        "b3".to_string(),
+        "b2".to_string(),
+        "b3".to_string(),
+        "unknown".to_string(),
+    ]);
+    assert_eq!(parents, ["b3", "b2"]);
+
+    // With normalization enabled, add_block drops redundant parents
+    let m = Block::new(
+        "m".to_string(),
+        vec!["genesis".to_string(), "b3".to_string(), "b2".to_string()],
+        vec![],
+        4,
+    );
+    dag.add_block(m).unwrap();
+    assert_eq!(dag.get_block("m").unwrap().parent_hashes, ["b3", "b2"]);
+}
+
+#[test]
+fn test_blockdag_with_params() {
+    let params = ConsensusParams::simnet().with_genesis(GenesisConfig {
+        hash: "simnet-genesis".to_string(),
+        ..GenesisConfig::new(1_000)
+    });
+    let mut dag = BlockDAG::with_params(params).unwrap();
+
+    assert_eq!(
+        dag.params().k,
+        recommended_k(10.0, 0.1, DEFAULT_SECURITY_DELTA).unwrap()
+    );
+    assert_eq!(dag.params().finality_depth, 100);
+    assert!(dag.get_block("genesis").is_none());
+    assert_eq!(dag.get_block("simnet-genesis").unwrap().timestamp, 1_000);
+
+    let b1 = Block::new(
+        "b1".to_string(),
+        vec!["simnet-genesis".to_string()],
+        vec![],
+        1_001,
+    );
+    dag.add_block(b1).unwrap();
+    assert_eq!(dag.get_block("b1").unwrap().weight, 2);
+
+    // simnet allows at most 5 parents
+    let too_many: Vec<String> = (0..6).map(|i| format!("p{}", i)).collect();
+    for (i, hash) in too_many.iter().enumerate() {
+        let parent = Block::new(
+            hash.clone(),
+            vec!["b1".to_string()],
+            vec![],
+            1_002 + i as u64,
+        );
+        dag.add_block(parent).unwrap();
+    }
+    let block = Block::new("b2".to_string(), too_many, vec![], 1_010);
+    assert_eq!(
+        dag.add_block(block).unwrap_err(),
+        RuleError::TooManyParents { count: 6, max: 5 }
+    );
+
+    // Inconsistent parameters are rejected at construction
+    let invalid = ConsensusParams {
+        max_block_parents: 0,
+        ..ConsensusParams::devnet()
+    };
+    assert!(BlockDAG::with_params(invalid).is_err());
+
+    // And by the setters
+    assert!(BlockDAG::new(3).with_max_block_parents(0).is_err());
+    assert!(BlockDAG::new(3).with_merge_depth(1_000_000).is_err());
+    assert!(BlockDAG::new(3).with_finality_depth(1).is_err());
+    assert!(BlockDAG::new(3).with_mergeset_size_limit(3).is_err());
+    let dag = BlockDAG::new(3).with_max_block_parents(4).unwrap();
+    assert!(dag.params().validate().is_ok());
+}
+
+#[test]
+fn test_save_and_reopen_from_disk() {
+    let path = std::env::temp_dir().join(format!("dag-demo-reopen-{}.log", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mut dag = BlockDAG::new(3)
+        .with_merge_depth(2)
+        .unwrap()
+        .with_finality_depth(2)
+        .unwrap();
+    dag.add_account("alice".to_string(), 1000).unwrap();
+    dag.add_account("bob".to_string(), 0).unwrap();
+    let mut parent = "genesis".to_string();
+    for i in 1..=4 {
+        let hash = format!("b{}", i);
+        let tx = Transaction::new(
+            format!("tx{}", i),
+            "alice".to_string(),
+            "bob".to_string(),
+            10,
+            i - 1,
+        );
+        let block = Block::new(hash.clone(), vec![parent], vec![tx], i);
+        dag.add_block(block).unwrap();
+        parent = hash;
+    }
+    let side = Block::new("side".to_string(), vec!["b2".to_string()], vec![], 3);
+    dag.add_block(side).unwrap();
+    dag.execute_blue_chain().unwrap();
+
+    let mut store = FileStore::open(&path).unwrap();
+    dag.save(&mut store).unwrap();
+    drop(store);
+
+    let reopened = BlockDAG::load(dag.params().clone(), &FileStore::open(&path).unwrap()).unwrap();
+    assert_eq!(reopened.get_all_blocks().len(), dag.get_all_blocks().len());
+    for block in dag.get_all_blocks() {
+        let loaded = reopened.get_block(&block.hash).unwrap();
+        assert_eq!(loaded.header(), block.header());
+        assert_eq!(loaded.transactions, block.transactions);
+        assert_eq!(loaded.color, block.color);
+        assert_eq!(loaded.weight, block.weight);
+    }
+    assert_eq!(reopened.state_commitment(), dag.state_commitment());
+    assert_eq!(reopened.get_account("bob").unwrap().balance, 40);
+    assert_eq!(reopened.mergeset("b3"), dag.mergeset("b3"));
+
+    // Saving after pruning removes pruned bodies but keeps their headers
+    dag.prune("b2").unwrap();
+    let mut store = FileStore::open(&path).unwrap();
+    dag.save(&mut store).unwrap();
+    let mut reopened = BlockDAG::load(dag.params().clone(), &store).unwrap();
+    assert_eq!(reopened.pruning_point(), "b2");
+    assert!(reopened.get_block("b1").is_none());
+    assert_eq!(
+        reopened.get_header("b1").unwrap().parent_hashes,
+        ["genesis"]
+    );
+
+    // The reopened DAG keeps accepting blocks
+    let b5 = Block::new("b5".to_string(), vec!["b4".to_string()], vec![], 5);
+    reopened.add_block(b5.clone()).unwrap();
+    dag.add_block(b5).unwrap();
+    assert_eq!(
+        reopened.get_block("b5").unwrap().weight,
+        dag.get_block("b5").unwrap().weight
+    );
+
+    // A store written for another genesis is rejected
+    let other = ConsensusParams::simnet().with_genesis(GenesisConfig::new(42));
+    assert!(BlockDAG::load(other, &store).is_err());
+    assert!(BlockDAG::load(ConsensusParams::simnet(), &MemoryStore::new()).is_err());
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+fn forked_dag() -> BlockDAG {
+    let mut dag = BlockDAG::new(1);
+    dag.add_account("alice".to_string(), 1000).unwrap();
+    dag.add_account("bob".to_string(), 0).unwrap();
+    let tx = Transaction::new(
+        "tx1".to_string(),
+        "alice".to_string(),
+        "bob".to_string(),
+        100,
+        0,
+    )
+    .with_fee(1);
+    let blocks = [
+        ("a", vec!["genesis"], vec![tx], 1),
+        ("b", vec!["genesis"], vec![], 2),
+        ("c", vec!["genesis"], vec![], 3),
+        ("d", vec!["a", "b", "c"], vec![], 4),
+    ];
+    for (hash, parents, txs, timestamp) in blocks {
+        let parents = parents.into_iter().map(String::from).collect();
+        dag.add_block(Block::new(hash.to_string(), parents, txs, timestamp))
+            .unwrap();
+    }
+    dag.execute_blue_chain().unwrap();
+    dag
+}
+
+#[test]
+fn test_snapshot_round_trip() {
+    let dag = forked_dag();
+    let snapshot = dag.snapshot();
+    assert_eq!(snapshot.blocks.len(), 5);
+    assert_eq!(snapshot.blocks[0].hash, "genesis");
+    assert_eq!(snapshot.accounts[0].address, "alice");
+
+    let restored = BlockDAG::from_snapshot(snapshot.clone()).unwrap();
+    for block in dag.get_all_blocks() {
+        let other = restored.get_block(&block.hash).unwrap();
+        assert_eq!(other.color, block.color);
+        assert_eq!(other.weight, block.weight);
+        assert_eq!(other.transactions, block.transactions);
+    }
+    assert_eq!(restored.state_commitment(), dag.state_commitment());
+
+    // Dangling parents and duplicates are rejected
+    let mut broken = snapshot.clone();
+    broken.blocks[4].parent_hashes.push("missing".to_string());
+    assert!(BlockDAG::from_snapshot(broken).is_err());
+    let mut broken = snapshot.clone();
+    broken.blocks.push(snapshot.blocks[1].clone());
+    assert!(BlockDAG::from_snapshot(broken).is_err());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_json_export_import() {
+    let dag = forked_dag();
+    let json = serde_json::to_string_pretty(&dag).unwrap();
+    assert!(json.contains("\"parent_hashes\""));
+    assert!(json.contains("\"color\": \"Blue\""));
+
+    let restored: BlockDAG = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored.get_all_blocks().len(), 5);
+    assert_eq!(restored.get_account("bob").unwrap().balance, 100);
+    assert_eq!(
+        serde_json::to_string_pretty(&restored).unwrap(),
+        json,
+        "export is deterministic"
+    );
+
+    let tx: Transaction = serde_json::from_str(
+        r#"{"id":"t","from":"a","to":"b","amount":1,"fee":0,"nonce":0,"status":{"Failed":"no funds"}}"#,
+    )
+    .unwrap();
+    assert_eq!(tx.status, TxStatus::Failed("no funds".to_string()));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_parallel_fork_fixture() {
+    let mut dag = BlockDAG::import_json(include_str!("fixtures/parallel_fork.json")).unwrap();
+    assert_eq!(dag.params().k, 3);
+    assert_eq!(dag.get_all_blocks().len(), 4);
+    assert_eq!(dag.get_block("b3").unwrap().parent_hashes, ["b1", "b2"]);
+
+    dag.execute_blue_chain().unwrap();
+    assert_eq!(
+        dag.get_account("alice").unwrap().balance,
+        1000 - 100 + 50 - 25
+    );
+    assert_eq!(dag.get_account("bob").unwrap().balance, 500 + 100 - 51 + 25);
+    assert_eq!(dag.receipt("tx2").unwrap().fee, 1);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_red_side_branch_fixture() {
+    let mut dag = BlockDAG::import_json(include_str!("fixtures/red_side_branch.json")).unwrap();
+    assert_eq!(dag.get_block("side").unwrap().color, BlockColor::Red);
+
+    dag.execute_blue_chain().unwrap();
+    assert_eq!(dag.accepting_block("side-tx"), Some("a3"));
+    assert_eq!(dag.get_account("alice").unwrap().balance, 1200);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_import_json_doc_example() {
+    // The example documented on `BlockDAG::import_json`
+    let json = r#"{
+      "version": 1,
+      "params": {
+        "k": 3,
+        "network_delay_ms": 0,
+        "genesis": { "hash": "genesis", "timestamp": 0 }
+      },
+      "accounts": [{ "address": "alice", "balance": 1000 }],
+      "blocks": [{
+        "hash": "b1",
+        "parents": ["genesis"],
+        "timestamp": 1,
+        "transactions": [{ "id": "tx1", "from": "alice", "to": "bob", "amount": 100, "nonce": 0 }]
+      }]
+    }"#;
+    let mut dag = BlockDAG::import_json(json).unwrap();
+    assert_eq!(dag.params().k, 3);
+    dag.execute_blue_chain().unwrap();
+    assert_eq!(dag.get_account("bob").unwrap().balance, 100);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_json_export_import_round_trip() {
+    let dag = forked_dag();
+    let json = dag.export_json().unwrap();
+    let restored = BlockDAG::import_json(&json).unwrap();
+
+    assert_eq!(restored.export_json().unwrap(), json);
+    assert_eq!(restored.genesis().commitment(), dag.genesis().commitment());
+    for block in dag.get_all_blocks() {
+        let other = restored.get_block(&block.hash).unwrap();
+        assert_eq!(other.parent_hashes, block.parent_hashes);
+        assert_eq!(other.color, block.color);
+        assert_eq!(other.weight, block.weight);
+    }
+
+    // Unknown versions, unknown parents and unordered blocks are rejected
+    assert!(BlockDAG::import_json(r#"{"version": 2}"#).is_err());
+    let Err(err) = BlockDAG::import_json(r#"{"version": 1, "params": {"k": 2}}"#) else {
+        panic!("invalid params accepted");
+    };
+    assert!(err.contains("Invalid DAG fixture params"), "{}", err);
+    let orphan = r#"{"version": 1, "blocks": [{"hash": "b", "parents": ["a"], "timestamp": 1}]}"#;
+    let Err(err) = BlockDAG::import_json(orphan) else {
+        panic!("orphan block accepted");
+    };
+    assert!(err.contains("Block 'b' rejected"));
+}
+
+#[test]
+fn test_dot_and_mermaid_export() {
+    let dag = forked_dag();
+    let selected = dag.selected_parent("d").unwrap().to_string();
+
+    let dot = dag.to_dot();
+    assert!(dot.starts_with("digraph BlockDAG {"));
+    assert!(dot.contains("\"genesis\" [label=\"genesis\\nblue #1\", fillcolor=\"#4a90d9\"];"));
+    assert!(dot.contains("[label=\"c\\nred\", fillcolor=\"#d9534f\"]"));
+    assert!(dot.contains(&format!(
+        "\"{}\" -> \"d\" [color=\"#1b4f8a\", penwidth=3];",
+        selected
+    )));
+    assert_eq!(dot.matches("penwidth=3").count(), 4);
+    assert_eq!(dot, dag.to_dot());
+
+    let clustered = dag.to_dot_with(&RenderOptions::new().with_timestamp_clusters(true));
+    assert!(clustered.contains("subgraph \"cluster_t4\" {"));
+    assert_eq!(clustered.matches("subgraph").count(), 5);
+
+    let mermaid = dag.to_mermaid();
+    assert!(mermaid.starts_with("graph LR\n"));
+    assert!(mermaid.contains("    b0[\"genesis<br/>blue #1\"]:::blue\n"));
+    assert!(mermaid.contains("\"c<br/>red\"]:::red"));
+    assert_eq!(mermaid.matches("==>").count(), 4);
+    assert_eq!(mermaid.matches("-->").count(), 2);
+
+    let clustered = dag.to_mermaid_with(&RenderOptions::new().with_timestamp_clusters(true));
+    assert!(clustered.contains("    subgraph t0[\"t=0\"]\n"));
+    assert_eq!(clustered.matches("    end\n").count(), 5);
+}
+
+#[test]
+fn test_save_rejects_records_load_would_reject() {
+    let mut dag = BlockDAG::new(3);
+    dag.add_account("alice".to_string(), 1000).unwrap();
+    let tx = Transaction::new(
+        "tx1".to_string(),
+        "alice".to_string(),
+        "b".repeat(2000),
+        10,
+        0,
+    );
+    let block = Block::new("b1".to_string(), vec!["genesis".to_string()], vec![tx], 1);
+    dag.add_block(block).unwrap();
+
+    // Encoding fails before anything is written, instead of load failing later
+    let mut store = MemoryStore::new();
+    let err = dag.save(&mut store).unwrap_err();
+    assert!(err.contains("b1"), "{}", err);
+    assert!(store.scan_prefix("").unwrap().is_empty());
+
+    let mut dag = BlockDAG::new(3);
+    let block = Block::new("h".repeat(2000), vec!["genesis".to_string()], vec![], 1);
+    dag.add_block(block).unwrap();
+    assert!(dag.save(&mut store).is_err());
+    assert!(BlockDAG::load(ConsensusParams::simnet(), &store).is_err());
+}